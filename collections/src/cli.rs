@@ -0,0 +1,49 @@
+//!
+//! What the subcommands of `collections` share: reading FILE or stdin,
+//! and turning the outcome of a command into its exit status.
+//!
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+
+/// The input of a command: the file at `path`, or stdin when `path` is
+/// missing or `-`. Returns the name to put in messages with it.
+pub fn open_input(path: Option<&str>) -> Result<(String, Box<dyn BufRead>), String> {
+    match path {
+        None | Some("-") => Ok(("stdin".to_string(), Box::new(io::stdin().lock()))),
+        Some(path) => {
+            let file = File::open(path).map_err(|e| format!("{}: {}", path, e))?;
+            Ok((path.to_string(), Box::new(BufReader::new(file))))
+        }
+    }
+}
+
+/// The exit status of the subcommand `name`: the one it asked for, or 1
+/// after printing its error.
+pub fn exit_status(name: &str, result: Result<i32, String>) -> i32 {
+    match result {
+        Ok(status) => status,
+        Err(err) => {
+            eprintln!("{}: {}", name, err);
+            1
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod testing {
+    use std::path::PathBuf;
+    use std::{env, fs, process};
+
+    pub fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    /// A directory for the test `name` to keep its files in, emptied of
+    /// whatever an earlier run left there.
+    pub fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("collections-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+}
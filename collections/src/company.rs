@@ -0,0 +1,389 @@
+//!
+//! The employee directory behind the REPL in `main`: who works in which
+//! department, and the commands that change or list it.
+//!
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+pub mod cli;
+mod command;
+mod employee;
+mod history;
+mod org;
+pub mod repl;
+pub mod report;
+mod search;
+pub mod server;
+pub mod session;
+pub mod store;
+
+pub use command::{Command, parse_command};
+pub use employee::{Employee, Field};
+pub use search::Hit;
+
+/// Why a change to the directory could not be made.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    NoDepartment(String),
+    NoEmployee {
+        name: String,
+        department: String,
+    },
+    NoSuchEmployee(String),
+    /// Several people share `name`; their IDs tell them apart.
+    Ambiguous {
+        name: String,
+        ids: Vec<u32>,
+    },
+    /// Several departments are called `name` but for case.
+    AmbiguousDepartment {
+        name: String,
+        departments: Vec<String>,
+    },
+    DepartmentExists(String),
+    InvalidValue {
+        field: Field,
+        msg: String,
+    },
+    /// The reporting loop a new manager would have closed.
+    Cycle(Vec<String>),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NoDepartment(department) => write!(f, "There is no department {}", department),
+            Error::NoEmployee { name, department } => {
+                write!(f, "{} does not work in {}", name, department)
+            }
+            Error::NoSuchEmployee(name) => write!(f, "There is no employee {}", name),
+            Error::Ambiguous { name, ids } => {
+                let ids: Vec<String> = ids.iter().map(|id| format!("#{}", id)).collect();
+                write!(
+                    f,
+                    "There are several employees called {}, use one of {}",
+                    name,
+                    ids.join(", ")
+                )
+            }
+            Error::AmbiguousDepartment { name, departments } => write!(
+                f,
+                "There are several departments called {}, use one of {}",
+                name,
+                departments.join(", ")
+            ),
+            Error::DepartmentExists(department) => {
+                write!(f, "There already is a department {}", department)
+            }
+            Error::InvalidValue { field, msg } => write!(f, "Invalid {}: {}", field, msg),
+            Error::Cycle(chain) => {
+                write!(f, "That would make a reporting loop: {}", chain.join(" → "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// The employees of a company and the departments they work in, for the
+/// text interface that takes commands like “Add Sally to Engineering” and
+/// lists the people in a department or in the whole company by department,
+/// sorted alphabetically.
+///
+/// Employees are kept in a `BTreeMap` by ID, and each department holds a
+/// `BTreeSet` roster of names and IDs, so every list comes out already
+/// sorted.
+///
+/// Wherever a method takes `who`, it is either a name or an ID written as
+/// `#7`. A name shared by several people is an `Error::Ambiguous`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Company {
+    employees: BTreeMap<u32, Employee>,
+    /// Every department, including the ones nobody works in any more, with
+    /// its employees kept in list order as they come and go.
+    departments: BTreeMap<String, Roster>,
+    next_id: u32,
+}
+
+/// The name and ID of everyone in a department, which sort the way lists
+/// are shown.
+type Roster = BTreeSet<(String, u32)>;
+
+impl Company {
+    pub fn new() -> Self {
+        Company {
+            employees: BTreeMap::new(),
+            departments: BTreeMap::new(),
+            next_id: 1,
+        }
+    }
+
+    /// Add a new employee and return their ID.
+    pub fn add_employee(&mut self, name: &str, department: &str) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.insert(Employee {
+            id,
+            name: name.to_string(),
+            department: department.to_string(),
+            title: None,
+            email: None,
+            start_date: None,
+            manager: None,
+        });
+        id
+    }
+
+    /// Store `employee`, replacing the record with the same ID, and put
+    /// them on the roster of their department, creating it if needed.
+    fn insert(&mut self, employee: Employee) {
+        self.remove(employee.id);
+        self.departments
+            .entry(employee.department.clone())
+            .or_default()
+            .insert((employee.name.clone(), employee.id));
+        self.employees.insert(employee.id, employee);
+    }
+
+    /// Drop the record with `id` and its roster entry. The department
+    /// stays.
+    fn remove(&mut self, id: u32) -> Option<Employee> {
+        let employee = self.employees.remove(&id)?;
+        if let Some(roster) = self.departments.get_mut(&employee.department) {
+            roster.remove(&(employee.name.clone(), id));
+        }
+        Some(employee)
+    }
+
+    /// The ID of the one employee `who` refers to, only looking at
+    /// `department` when given.
+    fn resolve(&self, who: &str, department: Option<&str>) -> Result<u32, Error> {
+        if let Some(id) = who.strip_prefix('#').and_then(|id| id.parse().ok()) {
+            return match self.employees.get(&id) {
+                Some(employee) if department.is_none_or(|d| d == employee.department) => Ok(id),
+                Some(_) => Err(Error::NoEmployee {
+                    name: who.to_string(),
+                    department: department.unwrap_or_default().to_string(),
+                }),
+                None => Err(Error::NoSuchEmployee(who.to_string())),
+            };
+        }
+        let ids: Vec<u32> = self
+            .employees
+            .values()
+            .filter(|e| e.name == who && department.is_none_or(|d| d == e.department))
+            .map(|e| e.id)
+            .collect();
+        match (ids.as_slice(), department) {
+            ([id], _) => Ok(*id),
+            ([], Some(department)) => Err(Error::NoEmployee {
+                name: who.to_string(),
+                department: department.to_string(),
+            }),
+            ([], None) => Err(Error::NoSuchEmployee(who.to_string())),
+            _ => Err(Error::Ambiguous {
+                name: who.to_string(),
+                ids,
+            }),
+        }
+    }
+
+    /// The employee `who` refers to.
+    pub fn find(&self, who: &str) -> Result<&Employee, Error> {
+        let id = self.resolve(who, None)?;
+        Ok(&self.employees[&id])
+    }
+
+    fn check_department(&self, department: &str) -> Result<(), Error> {
+        if !self.departments.contains_key(department) {
+            return Err(Error::NoDepartment(department.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Take `who` out of `department` and out of the company. The
+    /// department stays, even when it is left empty, and anyone reporting
+    /// to `who` now reports to their manager.
+    pub fn remove_employee(&mut self, who: &str, department: &str) -> Result<Employee, Error> {
+        self.check_department(department)?;
+        let id = self.resolve(who, Some(department))?;
+        Ok(self.take(id).expect("resolved IDs exist"))
+    }
+
+    /// Move `who` from one department to another, creating the new one if
+    /// needed.
+    pub fn move_employee(&mut self, who: &str, from: &str, to: &str) -> Result<u32, Error> {
+        self.check_department(from)?;
+        let id = self.resolve(who, Some(from))?;
+        let mut employee = self.remove(id).expect("resolved IDs exist");
+        employee.department = to.to_string();
+        self.insert(employee);
+        Ok(id)
+    }
+
+    /// Set or, with `None`, clear one of the optional fields of `who`.
+    pub fn set_field(
+        &mut self,
+        who: &str,
+        field: Field,
+        value: Option<&str>,
+    ) -> Result<u32, Error> {
+        let id = self.resolve(who, None)?;
+        let employee = self.employees.get_mut(&id).expect("resolved IDs exist");
+        employee
+            .set(field, value)
+            .map_err(|msg| Error::InvalidValue { field, msg })?;
+        Ok(id)
+    }
+
+    /// Give a department a new name that is not taken yet.
+    pub fn rename_department(&mut self, from: &str, to: &str) -> Result<(), Error> {
+        self.check_department(from)?;
+        if from != to && self.departments.contains_key(to) {
+            return Err(Error::DepartmentExists(to.to_string()));
+        }
+        let roster = self.departments.remove(from).expect("checked above");
+        for (_, id) in &roster {
+            let employee = self.employees.get_mut(id).expect("rostered IDs exist");
+            employee.department = to.to_string();
+        }
+        self.departments.insert(to.to_string(), roster);
+        Ok(())
+    }
+
+    /// Remove a department together with everyone in it, returning them.
+    pub fn delete_department(&mut self, department: &str) -> Result<Vec<Employee>, Error> {
+        self.check_department(department)?;
+        let roster = self.departments.remove(department).expect("checked above");
+        Ok(roster.iter().filter_map(|(_, id)| self.take(*id)).collect())
+    }
+
+    /// Every department in order.
+    pub fn departments(&self) -> impl Iterator<Item = &str> {
+        self.departments.keys().map(String::as_str)
+    }
+
+    fn roster<'a>(&'a self, roster: &'a Roster) -> Vec<&'a Employee> {
+        roster.iter().map(|(_, id)| &self.employees[id]).collect()
+    }
+
+    /// Everyone in `department`, sorted by name and then by ID.
+    pub fn get_employees(&self, department: &str) -> Option<Vec<&Employee>> {
+        self.departments
+            .get(department)
+            .map(|roster| self.roster(roster))
+    }
+
+    /// Every department in order, each with its sorted employees.
+    pub fn get_all_employees(&self) -> impl Iterator<Item = (&str, Vec<&Employee>)> {
+        self.departments
+            .iter()
+            .map(|(department, roster)| (department.as_str(), self.roster(roster)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn company() -> Company {
+        let mut company = Company::new();
+        company.add_employee("Sally", "Engineering");
+        company.add_employee("Amir", "Sales");
+        company.add_employee("Bob", "Sales");
+        company
+    }
+
+    fn names(company: &Company, department: &str) -> Vec<String> {
+        company
+            .get_employees(department)
+            .unwrap()
+            .iter()
+            .map(|e| e.name.clone())
+            .collect()
+    }
+
+    #[test]
+    fn remove_and_move() {
+        let mut company = company();
+        company.remove_employee("Bob", "Sales").unwrap();
+        assert_eq!(names(&company, "Sales"), vec!["Amir"]);
+        assert_eq!(
+            company.remove_employee("Bob", "Sales"),
+            Err(Error::NoEmployee {
+                name: "Bob".to_string(),
+                department: "Sales".to_string()
+            })
+        );
+        company.move_employee("Amir", "Sales", "Support").unwrap();
+        assert_eq!(names(&company, "Support"), vec!["Amir"]);
+        assert!(names(&company, "Sales").is_empty());
+        assert_eq!(
+            company.move_employee("Amir", "Marketing", "Sales"),
+            Err(Error::NoDepartment("Marketing".to_string()))
+        );
+    }
+
+    #[test]
+    fn rename_and_delete_departments() {
+        let mut company = company();
+        assert_eq!(
+            company.rename_department("Sales", "Engineering"),
+            Err(Error::DepartmentExists("Engineering".to_string()))
+        );
+        company.rename_department("Sales", "Revenue").unwrap();
+        assert!(company.get_employees("Sales").is_none());
+        assert_eq!(company.find("Amir").unwrap().department, "Revenue");
+        assert_eq!(company.delete_department("Revenue").unwrap().len(), 2);
+        assert_eq!(
+            company.delete_department("Revenue"),
+            Err(Error::NoDepartment("Revenue".to_string()))
+        );
+        assert!(company.find("Amir").is_err());
+    }
+
+    #[test]
+    fn duplicate_names_need_an_id() {
+        let mut company = company();
+        let second = company.add_employee("Sally", "Sales");
+        assert_eq!(
+            company.find("Sally"),
+            Err(Error::Ambiguous {
+                name: "Sally".to_string(),
+                ids: vec![1, second]
+            })
+        );
+        assert_eq!(
+            company.find(&format!("#{}", second)).unwrap().department,
+            "Sales"
+        );
+        assert!(matches!(company.find("#99"), Err(Error::NoSuchEmployee(_))));
+        // within one department the name is unique again
+        company.remove_employee("Sally", "Sales").unwrap();
+        assert_eq!(company.find("Sally").unwrap().id, 1);
+        // IDs are never reused
+        assert_eq!(company.add_employee("Sally", "Sales"), second + 1);
+    }
+
+    #[test]
+    fn sets_fields() {
+        let mut company = company();
+        company
+            .set_field("Amir", Field::Title, Some("Account Manager"))
+            .unwrap();
+        assert_eq!(
+            company.find("#2").unwrap().title.as_deref(),
+            Some("Account Manager")
+        );
+        assert!(matches!(
+            company.set_field("Amir", Field::Email, Some("nope")),
+            Err(Error::InvalidValue {
+                field: Field::Email,
+                ..
+            })
+        ));
+        company.set_field("Amir", Field::Title, None).unwrap();
+        assert_eq!(company.find("Amir").unwrap().title, None);
+    }
+}
@@ -0,0 +1,157 @@
+//!
+//! `collections run [--stop-on-error] SCRIPT`
+//!
+//! Runs the Company commands in SCRIPT (or stdin when it is `-`) one line
+//! at a time against the saved directory, the same as typing them into
+//! the REPL but without prompts. Blank lines and lines starting with `#`
+//! are skipped. What the commands print goes to stdout; errors go to
+//! stderr as `SCRIPT:LINE: message`, or `SCRIPT:LINE:COLUMN: message` for
+//! a line that does not parse.
+//!
+
+use std::io::{self, BufRead, Write};
+
+use super::parse_command;
+use super::session::Session;
+use super::store;
+use crate::cli;
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Options {
+    pub script: String,
+    pub stop_on_error: bool,
+}
+
+pub fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut script = None;
+    for arg in args {
+        match arg.as_str() {
+            "--stop-on-error" => options.stop_on_error = true,
+            flag if flag.starts_with("--") => return Err(format!("unknown option {}", flag)),
+            path if script.is_none() => script = Some(path.to_string()),
+            extra => return Err(format!("unexpected argument {}", extra)),
+        }
+    }
+    options.script = script.ok_or("missing script, give a file or -")?;
+    Ok(options)
+}
+
+/// Run every command read from `input`, called `name` in messages.
+/// Returns how many lines failed.
+pub fn run_script<R: BufRead, W: Write, E: Write>(
+    session: &mut Session,
+    name: &str,
+    input: R,
+    mut out: W,
+    mut err: E,
+    stop_on_error: bool,
+) -> io::Result<usize> {
+    let mut failed = 0;
+    for (n, line) in (1..).zip(input.lines()) {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.eq_ignore_ascii_case("quit") {
+            break;
+        }
+        // parsed here rather than in `Session::run`, so a syntax error
+        // gets a column instead of a caret
+        let result = match parse_command(line) {
+            Err(parse) => Err(format!("{}:{}:{}: {}", name, n, parse.column, parse.msg)),
+            Ok(command) => session
+                .run_command(line, command)
+                .map_err(|msg| format!("{}:{}: {}", name, n, msg)),
+        };
+        match result {
+            Ok(output) => writeln!(out, "{}", output)?,
+            Err(msg) => {
+                failed += 1;
+                writeln!(err, "{}", msg)?;
+                if stop_on_error {
+                    break;
+                }
+            }
+        }
+    }
+    Ok(failed)
+}
+
+/// Run the `run` command with the arguments after `run`. Returns the
+/// process exit status: 1 when any line failed.
+pub fn run(args: &[String]) -> i32 {
+    cli::exit_status("run", try_run(args).map(|failed| i32::from(failed > 0)))
+}
+
+fn try_run(args: &[String]) -> Result<usize, String> {
+    let options = parse_args(args)?;
+    let mut session = Session::open(store::default_path()).map_err(|e| e.to_string())?;
+    let (name, input) = cli::open_input(Some(&options.script))?;
+    let (stdout, stderr) = (io::stdout().lock(), io::stderr().lock());
+    run_script(
+        &mut session,
+        &name,
+        input,
+        stdout,
+        stderr,
+        options.stop_on_error,
+    )
+    .map_err(|e| format!("{}: {}", name, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::testing::{args, temp_dir};
+    use std::fs;
+
+    #[test]
+    fn parses_options() {
+        let options = parse_args(&args(&["--stop-on-error", "in.txt"])).unwrap();
+        assert_eq!(options.script, "in.txt");
+        assert!(options.stop_on_error);
+        assert!(parse_args(&args(&[])).is_err());
+        assert!(parse_args(&args(&["a", "b"])).is_err());
+    }
+
+    #[test]
+    fn runs_a_script() {
+        let dir = temp_dir("batch");
+        let script = "# onboarding\n\
+                      add Ann to Sales\n\
+                      \n\
+                      hire Bob\n\
+                      remove Bob from Sales\n\
+                      list employees in Sales\n";
+        let run = |stop| {
+            let mut session = Session::open(dir.join(format!("{}.json", stop))).unwrap();
+            let (mut out, mut err) = (Vec::new(), Vec::new());
+            let failed = run_script(
+                &mut session,
+                "s.txt",
+                script.as_bytes(),
+                &mut out,
+                &mut err,
+                stop,
+            );
+            let text = |bytes| String::from_utf8(bytes).unwrap();
+            (failed.unwrap(), text(out), text(err))
+        };
+
+        let (failed, out, err) = run(false);
+        assert_eq!(failed, 2);
+        assert_eq!(
+            out,
+            "Added Ann (#1) to Sales\nEmployees in Sales: Ann (#1)\n"
+        );
+        assert!(err.starts_with("s.txt:4:1: unknown command `hire`"));
+        assert!(err.ends_with("s.txt:5: Bob does not work in Sales\n"));
+
+        let (failed, out, _) = run(true);
+        assert_eq!(failed, 1);
+        assert_eq!(out, "Added Ann (#1) to Sales\n");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
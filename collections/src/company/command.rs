@@ -0,0 +1,699 @@
+//!
+//! The command language of the REPL:
+//!
+//! ```text
+//! add NAME to DEPT
+//! remove NAME from DEPT
+//! move NAME from DEPT to DEPT
+//! rename department DEPT to DEPT
+//! delete department DEPT
+//! list employees in DEPT
+//! list all employees
+//! set FIELD of WHO to VALUE
+//! clear FIELD of WHO
+//! show WHO
+//! set manager of WHO to WHO
+//! clear manager of WHO
+//! tree DEPT
+//! chain WHO
+//! find NAME
+//! headcount
+//! export csv | markdown
+//! undo | redo | history | help
+//! ```
+//!
+//! WHO is a name or an employee ID like `#7`; FIELD is `title`, `email`
+//! or `start date`. `tree` draws who reports to whom in a department and
+//! `chain` lists someone's managers up to the top.
+//!
+//! Keywords are case-insensitive. Names and departments may be several
+//! words (“Add Mary Jane to Human Resources”); a phrase ends at the next
+//! keyword, so one that contains a keyword itself has to be quoted:
+//! `add Sam to "Back to School"`. Inside quotes `\"` and `\\` stand for
+//! a quote and a backslash.
+//!
+
+use std::fmt;
+
+use super::Field;
+use super::report::Format;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Add {
+        name: String,
+        department: String,
+    },
+    Remove {
+        who: String,
+        department: String,
+    },
+    Move {
+        who: String,
+        from: String,
+        to: String,
+    },
+    RenameDepartment {
+        from: String,
+        to: String,
+    },
+    DeleteDepartment {
+        department: String,
+    },
+    ListDepartment {
+        department: String,
+    },
+    ListAll,
+    /// `value` is `None` for `clear`.
+    Set {
+        field: Field,
+        who: String,
+        value: Option<String>,
+    },
+    Show {
+        who: String,
+    },
+    /// `manager` is `None` for `clear`.
+    SetManager {
+        who: String,
+        manager: Option<String>,
+    },
+    Tree {
+        department: String,
+    },
+    Chain {
+        who: String,
+    },
+    /// People and departments whose names are like `query`.
+    Find {
+        query: String,
+    },
+    Headcount,
+    Export {
+        format: Format,
+    },
+    Undo,
+    Redo,
+    /// The last entries of the audit log.
+    History,
+    Help,
+}
+
+impl Command {
+    /// Whether running the command may change the directory, so it has to
+    /// be recorded for undo.
+    pub fn changes_directory(&self) -> bool {
+        !matches!(
+            self,
+            Command::ListDepartment { .. }
+                | Command::ListAll
+                | Command::Show { .. }
+                | Command::Tree { .. }
+                | Command::Chain { .. }
+                | Command::Find { .. }
+                | Command::Headcount
+                | Command::Export { .. }
+                | Command::Undo
+                | Command::Redo
+                | Command::History
+                | Command::Help
+        )
+    }
+}
+
+/// A command that could not be parsed, with the 1-based column of the
+/// word that was not expected.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub column: usize,
+    pub msg: String,
+}
+
+impl ParseError {
+    /// The input with a caret under the offending word and the message
+    /// below it.
+    pub fn render(&self, input: &str) -> String {
+        format!(
+            "{}\n{:>width$}\n{}",
+            input,
+            "^",
+            self.msg,
+            width = self.column
+        )
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "column {}: {}", self.column, self.msg)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Token {
+    text: String,
+    quoted: bool,
+    column: usize,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = (1..).zip(input.chars()).peekable();
+    while let Some(&(column, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let mut text = String::new();
+        if c == '"' {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some((_, '"')) => break,
+                    Some((_, '\\')) => match chars.next() {
+                        Some((_, c)) => text.push(c),
+                        None => break,
+                    },
+                    Some((_, c)) => text.push(c),
+                    None => {
+                        return Err(ParseError {
+                            column,
+                            msg: "this quote is never closed".to_string(),
+                        });
+                    }
+                }
+            }
+            tokens.push(Token {
+                text,
+                quoted: true,
+                column,
+            });
+            continue;
+        }
+        while let Some(&(_, c)) = chars.peek() {
+            if c.is_whitespace() || c == '"' {
+                break;
+            }
+            text.push(c);
+            chars.next();
+        }
+        tokens.push(Token {
+            text,
+            quoted: false,
+            column,
+        });
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    /// The column just after the input, for errors at its end.
+    end: usize,
+}
+
+impl Parser {
+    fn is_keyword(token: &Token, keyword: &str) -> bool {
+        !token.quoted && token.text.eq_ignore_ascii_case(keyword)
+    }
+
+    fn error<T>(&self, msg: String) -> Result<T, ParseError> {
+        let column = self.tokens.get(self.pos).map_or(self.end, |t| t.column);
+        Err(ParseError { column, msg })
+    }
+
+    /// The current word, for messages.
+    fn found(&self) -> String {
+        match self.tokens.get(self.pos) {
+            Some(token) => format!("`{}`", token.text),
+            None => "the end of the line".to_string(),
+        }
+    }
+
+    fn next_is(&self, keyword: &str) -> bool {
+        self.tokens
+            .get(self.pos)
+            .is_some_and(|token| Parser::is_keyword(token, keyword))
+    }
+
+    fn keyword(&mut self, keyword: &str) -> Result<(), ParseError> {
+        match self.tokens.get(self.pos) {
+            Some(token) if Parser::is_keyword(token, keyword) => {
+                self.pos += 1;
+                Ok(())
+            }
+            _ => self.error(format!("expected `{}`, found {}", keyword, self.found())),
+        }
+    }
+
+    /// One or more words, up to the keyword `until` or the end of the line.
+    fn phrase(&mut self, what: &str, until: Option<&str>) -> Result<String, ParseError> {
+        let start = self.pos;
+        while let Some(token) = self.tokens.get(self.pos) {
+            if until.is_some_and(|keyword| Parser::is_keyword(token, keyword)) {
+                break;
+            }
+            self.pos += 1;
+        }
+        let words: Vec<&str> = self.tokens[start..self.pos]
+            .iter()
+            .map(|t| t.text.as_str())
+            .collect();
+        let phrase = words.join(" ");
+        if phrase.is_empty() {
+            self.pos = start;
+            return self.error(format!("expected {}, found {}", what, self.found()));
+        }
+        Ok(phrase)
+    }
+
+    /// The name of an optional employee field, one or two words.
+    fn field(&mut self) -> Result<Field, ParseError> {
+        for len in [2, 1] {
+            let Some(words) = self.tokens.get(self.pos..self.pos + len) else {
+                continue;
+            };
+            let name: Vec<&str> = words.iter().map(|t| t.text.as_str()).collect();
+            if let Some(field) = Field::parse(&name.join(" ")) {
+                self.pos += len;
+                return Ok(field);
+            }
+        }
+        self.error(format!(
+            "expected a field (title, email or start date), found {}",
+            self.found()
+        ))
+    }
+
+    fn end(&self) -> Result<(), ParseError> {
+        if self.pos < self.tokens.len() {
+            return self.error(format!("unexpected {} at the end", self.found()));
+        }
+        Ok(())
+    }
+}
+
+/// The verbs a command can start with, for messages.
+const COMMANDS: &str = "add, remove, move, rename, delete, list, set, clear, show, tree, chain, \
+                        find, headcount, export, undo, redo, history or help";
+
+/// The verbs a command can start with, for completion.
+pub const VERBS: [&str; 18] = [
+    "add",
+    "remove",
+    "move",
+    "rename",
+    "delete",
+    "list",
+    "set",
+    "clear",
+    "show",
+    "tree",
+    "chain",
+    "find",
+    "headcount",
+    "export",
+    "undo",
+    "redo",
+    "history",
+    "help",
+];
+
+/// The other words of the grammar. `to`, `from` and `of` end a name in the
+/// commands that use them, so such a name has to be quoted there; the rest
+/// only mean something at their own place in a command. `quote` quotes any
+/// of them to be safe.
+pub const KEYWORDS: [&str; 13] = [
+    "to",
+    "from",
+    "in",
+    "of",
+    "department",
+    "employees",
+    "all",
+    "manager",
+    "title",
+    "email",
+    "start",
+    "csv",
+    "markdown",
+];
+
+/// `phrase` as it has to be typed: quoted when it contains a keyword.
+pub fn quote(phrase: &str) -> String {
+    let keyword = phrase
+        .split_whitespace()
+        .any(|word| KEYWORDS.iter().any(|k| k.eq_ignore_ascii_case(word)));
+    if keyword || phrase.contains('"') {
+        format!("\"{}\"", phrase.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        phrase.to_string()
+    }
+}
+
+/// What `help` prints.
+pub const HELP: &str = "\
+add NAME to DEPT
+remove WHO from DEPT
+move WHO from DEPT to DEPT
+rename department DEPT to DEPT
+delete department DEPT
+list employees in DEPT
+list all employees
+set FIELD of WHO to VALUE     FIELD is title, email or start date
+clear FIELD of WHO
+show WHO
+set manager of WHO to WHO
+clear manager of WHO
+tree DEPT
+chain WHO
+find NAME                     also parts of names and near misses
+headcount
+export csv | markdown
+undo | redo | history
+help | quit
+
+WHO is a name or #ID. Keywords are not case sensitive; quote names that
+contain one: add Sam to \"Back to School\"";
+
+/// Parse one line of the REPL.
+/// # Examples
+/// ```
+/// assert_eq!(
+///     parse_command("add Mary Jane to \"R&D\""),
+///     Ok(Command::Add { name: "Mary Jane".into(), department: "R&D".into() })
+/// );
+/// ```
+pub fn parse_command(input: &str) -> Result<Command, ParseError> {
+    let mut parser = Parser {
+        tokens: tokenize(input)?,
+        pos: 0,
+        end: input.chars().count() + 1,
+    };
+    let Some(first) = parser.tokens.first().filter(|t| !t.quoted) else {
+        return parser.error(format!(
+            "expected a command ({}), found {}",
+            COMMANDS,
+            parser.found()
+        ));
+    };
+    let verb = first.text.to_ascii_lowercase();
+    parser.pos += 1;
+    let command = match verb.as_str() {
+        "add" => Command::Add {
+            name: parser.phrase("a name", Some("to"))?,
+            department: {
+                parser.keyword("to")?;
+                parser.phrase("a department", None)?
+            },
+        },
+        "remove" => Command::Remove {
+            who: parser.phrase("a name", Some("from"))?,
+            department: {
+                parser.keyword("from")?;
+                parser.phrase("a department", None)?
+            },
+        },
+        "move" => Command::Move {
+            who: parser.phrase("a name", Some("from"))?,
+            from: {
+                parser.keyword("from")?;
+                parser.phrase("a department", Some("to"))?
+            },
+            to: {
+                parser.keyword("to")?;
+                parser.phrase("a department", None)?
+            },
+        },
+        "rename" => {
+            parser.keyword("department")?;
+            Command::RenameDepartment {
+                from: parser.phrase("a department", Some("to"))?,
+                to: {
+                    parser.keyword("to")?;
+                    parser.phrase("a department", None)?
+                },
+            }
+        }
+        "delete" => {
+            parser.keyword("department")?;
+            Command::DeleteDepartment {
+                department: parser.phrase("a department", None)?,
+            }
+        }
+        "set" | "clear" if parser.next_is("manager") => {
+            parser.pos += 1;
+            parser.keyword("of")?;
+            if verb == "clear" {
+                Command::SetManager {
+                    who: parser.phrase("a name or #ID", None)?,
+                    manager: None,
+                }
+            } else {
+                Command::SetManager {
+                    who: parser.phrase("a name or #ID", Some("to"))?,
+                    manager: {
+                        parser.keyword("to")?;
+                        Some(parser.phrase("a name or #ID", None)?)
+                    },
+                }
+            }
+        }
+        "set" => {
+            let field = parser.field()?;
+            parser.keyword("of")?;
+            Command::Set {
+                field,
+                who: parser.phrase("a name or #ID", Some("to"))?,
+                value: {
+                    parser.keyword("to")?;
+                    Some(parser.phrase("a value", None)?)
+                },
+            }
+        }
+        "clear" => {
+            let field = parser.field()?;
+            parser.keyword("of")?;
+            Command::Set {
+                field,
+                who: parser.phrase("a name or #ID", None)?,
+                value: None,
+            }
+        }
+        "show" => Command::Show {
+            who: parser.phrase("a name or #ID", None)?,
+        },
+        "tree" => Command::Tree {
+            department: parser.phrase("a department", None)?,
+        },
+        "chain" => Command::Chain {
+            who: parser.phrase("a name or #ID", None)?,
+        },
+        "find" => Command::Find {
+            query: parser.phrase("a name", None)?,
+        },
+        "headcount" => Command::Headcount,
+        "export" => {
+            let format = parser
+                .tokens
+                .get(parser.pos)
+                .and_then(|t| Format::parse(&t.text));
+            let Some(format) = format else {
+                return parser.error(format!(
+                    "expected `csv` or `markdown`, found {}",
+                    parser.found()
+                ));
+            };
+            parser.pos += 1;
+            Command::Export { format }
+        }
+        "undo" => Command::Undo,
+        "redo" => Command::Redo,
+        "history" => Command::History,
+        "help" => Command::Help,
+        "list" => match parser.tokens.get(parser.pos) {
+            Some(token) if Parser::is_keyword(token, "all") => {
+                parser.pos += 1;
+                parser.keyword("employees")?;
+                Command::ListAll
+            }
+            Some(token) if Parser::is_keyword(token, "employees") => {
+                parser.pos += 1;
+                parser.keyword("in")?;
+                Command::ListDepartment {
+                    department: parser.phrase("a department", None)?,
+                }
+            }
+            _ => {
+                return parser.error(format!(
+                    "expected `all` or `employees`, found {}",
+                    parser.found()
+                ));
+            }
+        },
+        _ => {
+            parser.pos = 0;
+            return parser.error(format!(
+                "unknown command {}, expected one of {}",
+                parser.found(),
+                COMMANDS
+            ));
+        }
+    };
+    parser.end()?;
+    Ok(command)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add(name: &str, department: &str) -> Command {
+        Command::Add {
+            name: name.to_string(),
+            department: department.to_string(),
+        }
+    }
+
+    fn column_of(input: &str) -> usize {
+        parse_command(input).unwrap_err().column
+    }
+
+    #[test]
+    fn multi_word_and_quoted_phrases() {
+        assert_eq!(
+            parse_command("Add Mary Jane to Human Resources"),
+            Ok(add("Mary Jane", "Human Resources"))
+        );
+        assert_eq!(
+            parse_command(r#"add Sam to "Back to School""#),
+            Ok(add("Sam", "Back to School"))
+        );
+        assert_eq!(
+            parse_command(r#"ADD "Dwayne \"The Rock\"" TO Film"#),
+            Ok(add("Dwayne \"The Rock\"", "Film"))
+        );
+        assert_eq!(
+            parse_command("move Ann Lee from Sales to Customer Care"),
+            Ok(Command::Move {
+                who: "Ann Lee".to_string(),
+                from: "Sales".to_string(),
+                to: "Customer Care".to_string(),
+            })
+        );
+        assert_eq!(
+            parse_command("Rename department Sales to Revenue"),
+            Ok(Command::RenameDepartment {
+                from: "Sales".to_string(),
+                to: "Revenue".to_string(),
+            })
+        );
+        assert_eq!(parse_command("list ALL employees"), Ok(Command::ListAll));
+    }
+
+    #[test]
+    fn parses_new_commands() {
+        assert_eq!(
+            parse_command("Delete department Sales"),
+            Ok(Command::DeleteDepartment {
+                department: "Sales".to_string()
+            })
+        );
+        assert_eq!(
+            parse_command("Remove Amir from Sales"),
+            Ok(Command::Remove {
+                who: "Amir".to_string(),
+                department: "Sales".to_string(),
+            })
+        );
+        assert!(parse_command("Remove Amir").is_err());
+    }
+
+    #[test]
+    fn reports_and_search() {
+        assert_eq!(
+            parse_command("find mary j"),
+            Ok(Command::Find {
+                query: "mary j".to_string()
+            })
+        );
+        assert_eq!(parse_command("HEADCOUNT"), Ok(Command::Headcount));
+        assert_eq!(
+            parse_command("export Markdown"),
+            Ok(Command::Export {
+                format: Format::Markdown
+            })
+        );
+        assert_eq!(parse_command("export pdf").unwrap_err().column, 8);
+    }
+
+    #[test]
+    fn employee_fields() {
+        assert_eq!(
+            parse_command("set start date of #3 to 2024-01-15"),
+            Ok(Command::Set {
+                field: Field::StartDate,
+                who: "#3".to_string(),
+                value: Some("2024-01-15".to_string()),
+            })
+        );
+        assert_eq!(
+            parse_command("Clear title of Mary Jane"),
+            Ok(Command::Set {
+                field: Field::Title,
+                who: "Mary Jane".to_string(),
+                value: None,
+            })
+        );
+        assert_eq!(
+            parse_command("show Mary Jane"),
+            Ok(Command::Show {
+                who: "Mary Jane".to_string()
+            })
+        );
+        assert_eq!(column_of("set salary of Sally to 1"), 5);
+        assert_eq!(
+            parse_command("set manager of Mary Jane to #1"),
+            Ok(Command::SetManager {
+                who: "Mary Jane".to_string(),
+                manager: Some("#1".to_string()),
+            })
+        );
+        assert_eq!(
+            parse_command("clear MANAGER of Bob"),
+            Ok(Command::SetManager {
+                who: "Bob".to_string(),
+                manager: None,
+            })
+        );
+        assert_eq!(
+            parse_command("tree Human Resources"),
+            Ok(Command::Tree {
+                department: "Human Resources".to_string()
+            })
+        );
+        assert_eq!(column_of("set title Sally"), 11);
+    }
+
+    #[test]
+    fn errors_point_at_the_unexpected_word() {
+        assert_eq!(column_of("Hire Sally"), 1);
+        assert_eq!(column_of("Add to Sales"), 5);
+        assert_eq!(column_of("Add Mary Jane"), 14);
+        assert_eq!(column_of("Rename Sales to Revenue"), 8);
+        assert_eq!(column_of("List all employees now"), 20);
+        assert_eq!(column_of("List everyone"), 6);
+        assert_eq!(column_of(r#"Add "Sally to Sales"#), 5);
+        assert_eq!(column_of(""), 1);
+
+        let err = parse_command("Add Mary Jane").unwrap_err();
+        assert_eq!(err.msg, "expected `to`, found the end of the line");
+        assert_eq!(
+            err.render("Add Mary Jane"),
+            "Add Mary Jane\n             ^\nexpected `to`, found the end of the line"
+        );
+    }
+}
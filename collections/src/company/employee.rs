@@ -0,0 +1,175 @@
+//!
+//! One person in the directory. Names need not be unique, so every
+//! employee gets an ID when added that never changes and is never reused;
+//! `#7` refers to employee 7 wherever a command takes a name.
+//!
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Employee {
+    pub id: u32,
+    pub name: String,
+    pub department: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    /// `YYYY-MM-DD`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_date: Option<String>,
+    /// The ID of the person this employee reports to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub manager: Option<u32>,
+}
+
+impl Employee {
+    /// “Sally (#3)”, for lists where two people may share a name.
+    pub fn label(&self) -> String {
+        format!("{} (#{})", self.name, self.id)
+    }
+
+    pub fn field(&self, field: Field) -> Option<&str> {
+        match field {
+            Field::Title => self.title.as_deref(),
+            Field::Email => self.email.as_deref(),
+            Field::StartDate => self.start_date.as_deref(),
+        }
+    }
+
+    fn field_mut(&mut self, field: Field) -> &mut Option<String> {
+        match field {
+            Field::Title => &mut self.title,
+            Field::Email => &mut self.email,
+            Field::StartDate => &mut self.start_date,
+        }
+    }
+
+    /// Set (or with `None` clear) one of the optional fields, checking the
+    /// value first.
+    pub fn set(&mut self, field: Field, value: Option<&str>) -> Result<(), String> {
+        if let Some(value) = value {
+            field.check(value)?;
+        }
+        *self.field_mut(field) = value.map(str::to_string);
+        Ok(())
+    }
+}
+
+impl fmt::Display for Employee {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "id         : #{}", self.id)?;
+        writeln!(f, "name       : {}", self.name)?;
+        write!(f, "department : {}", self.department)?;
+        for field in Field::ALL {
+            if let Some(value) = self.field(field) {
+                write!(f, "\n{:<11}: {}", field.to_string(), value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The optional fields of an `Employee`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Title,
+    Email,
+    StartDate,
+}
+
+impl Field {
+    pub const ALL: [Field; 3] = [Field::Title, Field::Email, Field::StartDate];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "title" => Some(Field::Title),
+            "email" => Some(Field::Email),
+            "start" | "start date" | "start-date" => Some(Field::StartDate),
+            _ => None,
+        }
+    }
+
+    fn check(self, value: &str) -> Result<(), String> {
+        match self {
+            Field::Title => Ok(()),
+            Field::Email => match value.split_once('@') {
+                Some((user, host)) if !user.is_empty() && host.contains('.') => Ok(()),
+                _ => Err(format!("`{}` is not an email address", value)),
+            },
+            Field::StartDate => check_date(value),
+        }
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Field::Title => "title",
+            Field::Email => "email",
+            Field::StartDate => "start date",
+        })
+    }
+}
+
+/// Accept a real calendar date written as `YYYY-MM-DD`.
+fn check_date(value: &str) -> Result<(), String> {
+    let invalid = || format!("`{}` is not a date like 2024-02-29", value);
+    let parts: Vec<&str> = value.split('-').collect();
+    let [year, month, day] = parts.as_slice() else {
+        return Err(invalid());
+    };
+    if year.len() != 4 || month.len() != 2 || day.len() != 2 {
+        return Err(invalid());
+    }
+    let (Ok(year), Ok(month), Ok(day)) = (
+        year.parse::<u32>(),
+        month.parse::<u32>(),
+        day.parse::<u32>(),
+    ) else {
+        return Err(invalid());
+    };
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return Err(invalid()),
+    };
+    if !(1..=days).contains(&day) {
+        return Err(invalid());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_field_values() {
+        let mut sally = Employee {
+            id: 1,
+            name: "Sally".to_string(),
+            department: "Engineering".to_string(),
+            title: None,
+            email: None,
+            start_date: None,
+            manager: None,
+        };
+        sally.set(Field::Email, Some("sally@example.com")).unwrap();
+        assert!(sally.set(Field::Email, Some("sally")).is_err());
+        sally.set(Field::StartDate, Some("2024-02-29")).unwrap();
+        assert!(sally.set(Field::StartDate, Some("2023-02-29")).is_err());
+        assert!(sally.set(Field::StartDate, Some("2024-1-5")).is_err());
+        assert_eq!(sally.field(Field::StartDate), Some("2024-02-29"));
+        sally.set(Field::StartDate, None).unwrap();
+        assert_eq!(
+            sally.to_string(),
+            "id         : #1\nname       : Sally\ndepartment : Engineering\nemail      : sally@example.com"
+        );
+    }
+}
@@ -0,0 +1,400 @@
+//!
+//! Undo, redo and the audit log.
+//!
+//! Every command that changes the directory is recorded as the list of
+//! `Change`s it made — each employee record, department and ID counter
+//! before and after — so it can be undone by applying the changes
+//! backwards. Each entry also goes to an append-only log of JSON lines
+//! with the time and the user, and replaying that log from an empty
+//! company rebuilds the directory.
+//!
+
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use super::store::Error;
+use super::{Company, Employee};
+
+/// One reversible step. `None` / `false` stand for “not there”.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Change {
+    Employee {
+        before: Option<Box<Employee>>,
+        after: Option<Box<Employee>>,
+    },
+    Department {
+        name: String,
+        before: bool,
+        after: bool,
+    },
+    NextId {
+        before: u32,
+        after: u32,
+    },
+}
+
+impl Change {
+    fn inverse(&self) -> Change {
+        match self.clone() {
+            Change::Employee { before, after } => Change::Employee {
+                before: after,
+                after: before,
+            },
+            Change::Department {
+                name,
+                before,
+                after,
+            } => Change::Department {
+                name,
+                before: after,
+                after: before,
+            },
+            Change::NextId { before, after } => Change::NextId {
+                before: after,
+                after: before,
+            },
+        }
+    }
+
+    fn apply(&self, company: &mut Company) {
+        match self {
+            Change::Employee { before, after } => match (before, after) {
+                (_, Some(employee)) => company.insert((**employee).clone()),
+                (Some(employee), None) => {
+                    company.remove(employee.id);
+                }
+                (None, None) => {}
+            },
+            Change::Department { name, after, .. } => {
+                if *after {
+                    company.departments.entry(name.clone()).or_default();
+                } else {
+                    company.departments.remove(name);
+                }
+            }
+            Change::NextId { after, .. } => company.next_id = *after,
+        }
+    }
+}
+
+/// Everything that differs between two states of the directory.
+pub fn diff(before: &Company, after: &Company) -> Vec<Change> {
+    let mut changes = Vec::new();
+    let ids = before.employees.keys().chain(after.employees.keys());
+    let mut ids: Vec<u32> = ids.copied().collect();
+    ids.sort_unstable();
+    ids.dedup();
+    for id in ids {
+        let (old, new) = (before.employees.get(&id), after.employees.get(&id));
+        if old != new {
+            changes.push(Change::Employee {
+                before: old.cloned().map(Box::new),
+                after: new.cloned().map(Box::new),
+            });
+        }
+    }
+    let gone = before.departments.keys().map(|name| (name, true, false));
+    let new = after.departments.keys().map(|name| (name, false, true));
+    for (name, was, is) in gone.chain(new) {
+        let other = if was { after } else { before };
+        if !other.departments.contains_key(name) {
+            changes.push(Change::Department {
+                name: name.clone(),
+                before: was,
+                after: is,
+            });
+        }
+    }
+    if before.next_id != after.next_id {
+        changes.push(Change::NextId {
+            before: before.next_id,
+            after: after.next_id,
+        });
+    }
+    changes
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    /// The directory as it was when the log was started.
+    Import,
+    Do,
+    Undo,
+    Redo,
+}
+
+/// One line of the audit log. `changes` are the ones actually applied, so
+/// for an undo they are already reversed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    /// Seconds since the Unix epoch.
+    pub time: u64,
+    pub user: String,
+    pub action: Action,
+    pub command: String,
+    pub changes: Vec<Change>,
+}
+
+/// The undo and redo stacks of one session, and the log they write to.
+pub struct History {
+    log: PathBuf,
+    user: String,
+    undo: Vec<Entry>,
+    redo: Vec<Entry>,
+}
+
+impl History {
+    pub fn new(log: PathBuf) -> Self {
+        let user = env::var("USER")
+            .or_else(|_| env::var("USERNAME"))
+            .unwrap_or_else(|_| "unknown".to_string());
+        History {
+            log,
+            user,
+            undo: Vec::new(),
+            redo: Vec::new(),
+        }
+    }
+
+    pub fn log(&self) -> &Path {
+        &self.log
+    }
+
+    fn append(&self, action: Action, command: &str, changes: Vec<Change>) -> Result<Entry, Error> {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let entry = Entry {
+            time,
+            user: self.user.clone(),
+            action,
+            command: command.to_string(),
+            changes,
+        };
+        let io_error = |source| Error::Io {
+            path: self.log.clone(),
+            source,
+        };
+        if let Some(dir) = self.log.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).map_err(io_error)?;
+        }
+        let mut line = serde_json::to_string(&entry).expect("an entry always serializes");
+        line.push('\n');
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.log)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .map_err(io_error)?;
+        Ok(entry)
+    }
+
+    /// Start the log with the whole directory when there is none yet, so
+    /// replaying it gives back what was there before logging began.
+    pub fn import(&self, company: &Company) -> Result<(), Error> {
+        let changes = diff(&Company::new(), company);
+        if self.log.exists() || changes.is_empty() {
+            return Ok(());
+        }
+        self.append(Action::Import, "", changes)?;
+        Ok(())
+    }
+
+    /// Log `changes`, made by `command`, and make them the next undo.
+    pub fn record(&mut self, command: &str, changes: Vec<Change>) -> Result<(), Error> {
+        let entry = self.append(Action::Do, command, changes)?;
+        self.undo.push(entry);
+        self.redo.clear();
+        Ok(())
+    }
+
+    /// Reverse the last command. Returns it, or `None` when there is
+    /// nothing left to undo.
+    pub fn undo(&mut self, company: &mut Company) -> Result<Option<String>, Error> {
+        let Some(entry) = self.undo.pop() else {
+            return Ok(None);
+        };
+        let inverse: Vec<Change> = entry.changes.iter().rev().map(Change::inverse).collect();
+        // log first: a change that is not in the log must not happen
+        if let Err(err) = self.append(Action::Undo, &entry.command, inverse.clone()) {
+            self.undo.push(entry);
+            return Err(err);
+        }
+        for change in &inverse {
+            change.apply(company);
+        }
+        let command = entry.command.clone();
+        self.redo.push(entry);
+        Ok(Some(command))
+    }
+
+    /// Take back the last command for good, e.g. because the directory
+    /// could not be saved after it. The log gets an undo entry for it, but
+    /// unlike `undo` it cannot be redone.
+    pub fn retract(&mut self, company: &mut Company) -> Result<(), Error> {
+        if self.undo(company)?.is_some() {
+            self.redo.pop();
+        }
+        Ok(())
+    }
+
+    /// Make the last undone command again.
+    pub fn redo(&mut self, company: &mut Company) -> Result<Option<String>, Error> {
+        let Some(entry) = self.redo.pop() else {
+            return Ok(None);
+        };
+        let logged = match self.append(Action::Redo, &entry.command, entry.changes.clone()) {
+            Ok(logged) => logged,
+            Err(err) => {
+                self.redo.push(entry);
+                return Err(err);
+            }
+        };
+        for change in &logged.changes {
+            change.apply(company);
+        }
+        let command = logged.command.clone();
+        self.undo.push(logged);
+        Ok(Some(command))
+    }
+}
+
+/// Every entry of the log at `path`; none when nothing has been logged
+/// yet.
+pub fn read_log(path: &Path) -> Result<Vec<Entry>, Error> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(source) => {
+            return Err(Error::Io {
+                path: path.to_path_buf(),
+                source,
+            });
+        }
+    };
+    (1..)
+        .zip(text.lines())
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(n, line)| {
+            serde_json::from_str(line).map_err(|e| Error::Corrupt {
+                path: path.to_path_buf(),
+                msg: format!("line {}: {}", n, e),
+            })
+        })
+        .collect()
+}
+
+/// Rebuild the directory from nothing by applying every logged change.
+pub fn replay(entries: &[Entry]) -> Company {
+    let mut company = Company::new();
+    for entry in entries {
+        for change in &entry.changes {
+            change.apply(&mut company);
+        }
+    }
+    company
+}
+
+/// The log that goes with the company file at `path`:
+/// `company.json` → `company.log`.
+pub fn log_path(path: &Path) -> PathBuf {
+    path.with_extension("log")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::company::Field;
+    use std::process;
+
+    fn temp_log(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("collections-history-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    /// Run `f` on `company` and record what it changed.
+    fn run(history: &mut History, company: &mut Company, f: impl FnOnce(&mut Company)) {
+        let before = company.clone();
+        f(company);
+        history.record("test", diff(&before, company)).unwrap();
+    }
+
+    #[test]
+    fn undo_redo_and_replay() {
+        let log = temp_log("undo.log");
+        let mut history = History::new(log.clone());
+        let mut company = Company::new();
+        company.add_employee("Ann", "Engineering");
+        history.import(&company).unwrap();
+
+        run(&mut history, &mut company, |c| {
+            c.add_employee("Bob", "Sales");
+        });
+        run(&mut history, &mut company, |c| {
+            c.set_manager("Bob", Some("Ann")).unwrap();
+            c.set_field("Bob", Field::Title, Some("Rep")).unwrap();
+        });
+        run(&mut history, &mut company, |c| {
+            c.delete_department("Engineering").unwrap();
+        });
+        let after = company.clone();
+
+        assert!(history.undo(&mut company).unwrap().is_some());
+        assert_eq!(company.find("Bob").unwrap().manager, Some(1));
+        assert!(history.undo(&mut company).unwrap().is_some());
+        assert_eq!(company.find("Bob").unwrap().title, None);
+        assert!(history.redo(&mut company).unwrap().is_some());
+        assert!(history.redo(&mut company).unwrap().is_some());
+        assert_eq!(company, after);
+        assert_eq!(history.redo(&mut company).unwrap(), None);
+
+        history.undo(&mut company).unwrap();
+        history.undo(&mut company).unwrap();
+        history.undo(&mut company).unwrap();
+        assert_eq!(history.undo(&mut company).unwrap(), None);
+        // the import cannot be undone
+        assert_eq!(company.departments().count(), 1);
+
+        let entries = read_log(&log).unwrap();
+        assert_eq!(entries.len(), 1 + 3 + 2 + 2 + 3);
+        assert_eq!(entries[0].action, Action::Import);
+        assert_eq!(replay(&entries), company);
+        assert_eq!(replay(&entries[..4]), after);
+        assert_eq!(replay(&entries[..8]), after);
+        fs::remove_file(&log).unwrap();
+    }
+
+    #[test]
+    fn a_new_command_clears_redo() {
+        let mut history = History::new(temp_log("redo.log"));
+        let mut company = Company::new();
+        run(&mut history, &mut company, |c| {
+            c.add_employee("Ann", "Sales");
+        });
+        history.undo(&mut company).unwrap();
+        run(&mut history, &mut company, |c| {
+            c.add_employee("Bob", "Sales");
+        });
+        assert_eq!(history.redo(&mut company).unwrap(), None);
+        fs::remove_file(history.log()).unwrap();
+    }
+
+    #[test]
+    fn corrupt_log_lines_are_reported() {
+        let log = temp_log("corrupt.log");
+        fs::write(&log, "{\"time\": 1}\n").unwrap();
+        let err = read_log(&log).unwrap_err();
+        assert!(err.to_string().contains("line 1"));
+        fs::remove_file(&log).unwrap();
+    }
+}
@@ -0,0 +1,208 @@
+//!
+//! Who reports to whom. Every employee has at most one manager, who may
+//! work in another department; assigning a manager that would close a
+//! loop (Ann → Bob → Ann) is refused. When someone leaves, the people
+//! reporting to them move up to their manager.
+//!
+
+use super::{Company, Employee, Error};
+
+impl Company {
+    /// Make `manager` the manager of `who`, or with `None` leave `who`
+    /// without one. Returns the ID of `who`.
+    pub fn set_manager(&mut self, who: &str, manager: Option<&str>) -> Result<u32, Error> {
+        let id = self.resolve(who, None)?;
+        let manager = match manager {
+            Some(manager) => Some(self.resolve(manager, None)?),
+            None => None,
+        };
+        if let Some(manager) = manager {
+            // walking up from the new manager must not reach `who`
+            let mut chain = vec![manager];
+            let mut current = manager;
+            while current != id {
+                match self.employees[&current].manager {
+                    Some(next) => {
+                        chain.push(next);
+                        current = next;
+                    }
+                    None => break,
+                }
+            }
+            if current == id {
+                let mut names = vec![self.employees[&id].label()];
+                names.extend(chain.iter().map(|id| self.employees[id].label()));
+                return Err(Error::Cycle(names));
+            }
+        }
+        self.employees
+            .get_mut(&id)
+            .expect("resolved IDs exist")
+            .manager = manager;
+        Ok(id)
+    }
+
+    /// `who` followed by their manager, their manager's manager and so on
+    /// up to someone without one.
+    pub fn chain_of_command(&self, who: &str) -> Result<Vec<&Employee>, Error> {
+        let mut employee = &self.employees[&self.resolve(who, None)?];
+        let mut chain = vec![employee];
+        while let Some(manager) = employee.manager {
+            employee = &self.employees[&manager];
+            chain.push(employee);
+        }
+        Ok(chain)
+    }
+
+    /// The people reporting directly to the employee with `id`, sorted by
+    /// name.
+    pub fn reports(&self, id: u32) -> Vec<&Employee> {
+        let mut reports: Vec<&Employee> = self
+            .employees
+            .values()
+            .filter(|e| e.manager == Some(id))
+            .collect();
+        reports.sort_by(|a, b| a.name.cmp(&b.name).then(a.id.cmp(&b.id)));
+        reports
+    }
+
+    /// The reporting lines inside `department` as an indented tree. People
+    /// whose manager works elsewhere (or who have none) are at the top.
+    pub fn reporting_tree(&self, department: &str) -> Result<String, Error> {
+        let employees = self
+            .get_employees(department)
+            .ok_or_else(|| Error::NoDepartment(department.to_string()))?;
+        let mut out = department.to_string();
+        let roots: Vec<&Employee> = employees
+            .iter()
+            .copied()
+            .filter(|e| {
+                e.manager
+                    .is_none_or(|m| self.employees[&m].department != department)
+            })
+            .collect();
+        for (i, root) in roots.iter().enumerate() {
+            self.draw(root, department, "", i + 1 == roots.len(), &mut out);
+        }
+        Ok(out)
+    }
+
+    fn draw(
+        &self,
+        employee: &Employee,
+        department: &str,
+        indent: &str,
+        last: bool,
+        out: &mut String,
+    ) {
+        let (branch, next_indent) = if last {
+            ("└── ", "    ")
+        } else {
+            ("├── ", "│   ")
+        };
+        out.push('\n');
+        out.push_str(indent);
+        out.push_str(branch);
+        out.push_str(&employee.label());
+        let outside = employee
+            .manager
+            .map(|m| &self.employees[&m])
+            .filter(|manager| manager.department != department);
+        if let Some(manager) = outside {
+            out.push_str(&format!(
+                " (reports to {} in {})",
+                manager.label(),
+                manager.department
+            ));
+        }
+        let reports: Vec<&Employee> = self
+            .reports(employee.id)
+            .into_iter()
+            .filter(|e| e.department == department)
+            .collect();
+        let indent = format!("{}{}", indent, next_indent);
+        for (i, report) in reports.iter().enumerate() {
+            self.draw(report, department, &indent, i + 1 == reports.len(), out);
+        }
+    }
+
+    /// Remove the employee with `id`, handing their reports to their own
+    /// manager.
+    pub(super) fn take(&mut self, id: u32) -> Option<Employee> {
+        let employee = self.remove(id)?;
+        for report in self.employees.values_mut() {
+            if report.manager == Some(id) {
+                report.manager = employee.manager;
+            }
+        }
+        Some(employee)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ann runs Engineering with Bob and Cid under her; Dee in Sales
+    /// reports to Bob.
+    fn company() -> Company {
+        let mut company = Company::new();
+        company.add_employee("Ann", "Engineering");
+        company.add_employee("Bob", "Engineering");
+        company.add_employee("Cid", "Engineering");
+        company.add_employee("Dee", "Sales");
+        company.set_manager("Bob", Some("Ann")).unwrap();
+        company.set_manager("Cid", Some("Ann")).unwrap();
+        company.set_manager("Dee", Some("Bob")).unwrap();
+        company
+    }
+
+    #[test]
+    fn refuses_cycles() {
+        let mut company = company();
+        assert_eq!(
+            company.set_manager("Ann", Some("Dee")),
+            Err(Error::Cycle(vec![
+                "Ann (#1)".to_string(),
+                "Dee (#4)".to_string(),
+                "Bob (#2)".to_string(),
+                "Ann (#1)".to_string(),
+            ]))
+        );
+        assert!(matches!(
+            company.set_manager("Ann", Some("Ann")),
+            Err(Error::Cycle(_))
+        ));
+        company.set_manager("Dee", None).unwrap();
+        company.set_manager("Ann", Some("Dee")).unwrap();
+    }
+
+    #[test]
+    fn chain_and_tree() {
+        let company = company();
+        let chain: Vec<String> = company
+            .chain_of_command("Dee")
+            .unwrap()
+            .iter()
+            .map(|e| e.name.clone())
+            .collect();
+        assert_eq!(chain, vec!["Dee", "Bob", "Ann"]);
+        assert_eq!(
+            company.reporting_tree("Engineering").unwrap(),
+            "Engineering\n└── Ann (#1)\n    ├── Bob (#2)\n    └── Cid (#3)"
+        );
+        assert_eq!(
+            company.reporting_tree("Sales").unwrap(),
+            "Sales\n└── Dee (#4) (reports to Bob (#2) in Engineering)"
+        );
+    }
+
+    #[test]
+    fn reports_move_up_when_their_manager_leaves() {
+        let mut company = company();
+        company.remove_employee("Bob", "Engineering").unwrap();
+        assert_eq!(company.find("Dee").unwrap().manager, Some(1));
+        company.delete_department("Engineering").unwrap();
+        assert_eq!(company.find("Dee").unwrap().manager, None);
+    }
+}
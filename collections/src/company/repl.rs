@@ -0,0 +1,229 @@
+//!
+//! The interactive REPL: a line editor with history kept between
+//! sessions and tab completion of command words, departments and
+//! employee names from the current directory.
+//!
+
+use std::path::PathBuf;
+
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+
+use super::command::{KEYWORDS, VERBS, quote};
+use super::session::Session;
+use super::{Company, store};
+
+/// What the REPL can complete, refreshed after every command.
+#[derive(Debug, Default)]
+pub struct Completion {
+    departments: Vec<String>,
+    names: Vec<String>,
+}
+
+impl Completion {
+    pub fn refresh(&mut self, company: &Company) {
+        self.departments.clear();
+        self.names.clear();
+        for (department, employees) in company.get_all_employees() {
+            self.names.extend(employees.iter().map(|e| e.name.clone()));
+            self.departments.push(department.to_string());
+        }
+        self.names.sort();
+        self.names.dedup();
+    }
+
+    /// Where the completed text starts in `line`, and what may go there.
+    ///
+    /// The first word completes to a command. After it, names and
+    /// departments are matched against the longest run of words before
+    /// the cursor that starts one, so `Mary J` completes to `Mary Jane`;
+    /// keywords only against the last word.
+    fn candidates(&self, line: &str, pos: usize) -> (usize, Vec<String>) {
+        let line = &line[..pos];
+        let starts: Vec<usize> = line
+            .char_indices()
+            .filter(|&(i, c)| {
+                !c.is_whitespace() && (i == 0 || line[..i].ends_with(char::is_whitespace))
+            })
+            .map(|(i, _)| i)
+            .chain(line.ends_with(char::is_whitespace).then_some(pos))
+            .collect();
+        let matches =
+            |typed: &str, word: &str| word.to_lowercase().starts_with(&typed.to_lowercase());
+        for (n, &start) in starts.iter().enumerate() {
+            let typed = &line[start..];
+            let mut found: Vec<String> = if n == 0 {
+                VERBS
+                    .iter()
+                    .chain(&["quit"])
+                    .filter(|verb| matches(typed, verb))
+                    .map(|verb| verb.to_string())
+                    .collect()
+            } else {
+                let phrases = self.names.iter().chain(&self.departments);
+                let mut found: Vec<String> = phrases
+                    .filter(|phrase| !typed.is_empty() && matches(typed, phrase))
+                    .map(|phrase| quote(phrase))
+                    .collect();
+                if n + 1 == starts.len() {
+                    let keywords = KEYWORDS.iter().filter(|word| matches(typed, word));
+                    found.extend(keywords.map(|word| word.to_string()));
+                }
+                found
+            };
+            if !found.is_empty() {
+                found.sort();
+                found.dedup();
+                return (start, found);
+            }
+        }
+        (pos, Vec::new())
+    }
+}
+
+impl Completer for Completion {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(self.candidates(line, pos))
+    }
+}
+
+impl Hinter for Completion {
+    type Hint = String;
+}
+
+impl Highlighter for Completion {}
+
+impl Validator for Completion {}
+
+impl Helper for Completion {}
+
+/// Where the lines typed into the REPL are kept between sessions: next to
+/// the company file.
+pub fn history_path() -> PathBuf {
+    store::default_path().with_file_name("repl_history")
+}
+
+/// Run the REPL until `quit` or the end of input. Returns the process
+/// exit status.
+pub fn run() -> i32 {
+    let mut session = match Session::open(store::default_path()) {
+        Ok(session) => session,
+        Err(err) => {
+            eprintln!("{}", err);
+            return 1;
+        }
+    };
+    let mut editor: Editor<Completion, DefaultHistory> = match Editor::new() {
+        Ok(editor) => editor,
+        Err(err) => {
+            eprintln!("{}", err);
+            return 1;
+        }
+    };
+    let mut completion = Completion::default();
+    completion.refresh(session.company());
+    editor.set_helper(Some(completion));
+    let history = history_path();
+    // there is no history yet the first time
+    let _ = editor.load_history(&history);
+
+    println!("Company directory: type help for the commands, quit to exit");
+    loop {
+        let input = match editor.readline("> ") {
+            Ok(input) => input,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(err) => {
+                eprintln!("{}", err);
+                break;
+            }
+        };
+        let input = input.trim();
+        if input.is_empty() {
+            continue;
+        }
+        let _ = editor.add_history_entry(input);
+        if input.eq_ignore_ascii_case("quit") {
+            break;
+        }
+        match session.run(input) {
+            Ok(output) | Err(output) => println!("{}", output),
+        }
+        if let Some(completion) = editor.helper_mut() {
+            completion.refresh(session.company());
+        }
+    }
+    if let Some(dir) = history.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    if let Err(err) = editor.save_history(&history) {
+        eprintln!("{}: {}", history.display(), err);
+    }
+    println!("Exit");
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn completion() -> Completion {
+        let mut company = Company::new();
+        company.add_employee("Mary Jane", "Sales");
+        company.add_employee("Mark", "Back to School");
+        company.add_employee("Mary Jane", "Support");
+        let mut completion = Completion::default();
+        completion.refresh(&company);
+        completion
+    }
+
+    fn complete(line: &str) -> (usize, Vec<String>) {
+        completion().candidates(line, line.len())
+    }
+
+    #[test]
+    fn completes_commands_names_and_keywords() {
+        assert_eq!(
+            complete("re"),
+            (0, vec!["redo".into(), "remove".into(), "rename".into()])
+        );
+        assert_eq!(
+            complete("show ma"),
+            (
+                5,
+                vec![
+                    "Mark".into(),
+                    "Mary Jane".into(),
+                    "manager".into(),
+                    "markdown".into()
+                ]
+            )
+        );
+        assert_eq!(complete("show Mary J"), (5, vec!["Mary Jane".into()]));
+        assert_eq!(
+            complete("move Mark from b"),
+            (15, vec!["\"Back to School\"".into()])
+        );
+        assert_eq!(
+            complete("add Ann t"),
+            (8, vec!["title".into(), "to".into()])
+        );
+        assert_eq!(
+            complete("add Ann to S").1,
+            vec!["Sales", "Support", "start"]
+        );
+        assert_eq!(complete("list xyz"), (8, vec![]));
+    }
+}
@@ -0,0 +1,189 @@
+//!
+//! Reports over the whole directory: how many people work in each
+//! department, and the directory as CSV or as a Markdown table, sorted by
+//! department, name and ID like `list all employees`.
+//!
+
+use super::{Company, Employee};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Csv,
+    Markdown,
+}
+
+impl Format {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "csv" => Some(Format::Csv),
+            "markdown" | "md" => Some(Format::Markdown),
+            _ => None,
+        }
+    }
+}
+
+/// A CSV field, quoted when it has to be.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// A Markdown table cell: line breaks would end the row, and the
+/// characters that end the cell or start emphasis, code or a link are
+/// escaped.
+fn markdown_cell(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\n' | '\r' => out.push(' '),
+            '\\' | '|' | '*' | '_' | '`' | '[' | ']' | '<' | '>' => {
+                out.push('\\');
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+impl Company {
+    /// Every department with the number of people in it.
+    pub fn headcounts(&self) -> Vec<(&str, usize)> {
+        self.departments
+            .iter()
+            .map(|(department, roster)| (department.as_str(), roster.len()))
+            .collect()
+    }
+
+    /// The headcounts as a table, with the total at the bottom.
+    pub fn headcount_report(&self) -> String {
+        let counts = self.headcounts();
+        let width = counts
+            .iter()
+            .map(|(department, _)| department.chars().count())
+            .chain([5])
+            .max()
+            .unwrap_or_default();
+        let mut out = String::new();
+        for (department, count) in &counts {
+            out.push_str(&format!("{:<width$}  {:>4}\n", department, count));
+        }
+        let total: usize = counts.iter().map(|(_, count)| count).sum();
+        out.push_str(&format!("{:<width$}  {:>4}", "Total", total));
+        out
+    }
+
+    /// One row per employee: department, ID, name, the optional fields and
+    /// the manager.
+    fn rows(&self) -> Vec<[String; 7]> {
+        let manager = |employee: &Employee| employee.manager.map(|id| &self.employees[&id]);
+        self.get_all_employees()
+            .flat_map(|(_, employees)| employees)
+            .map(|e| {
+                [
+                    e.department.clone(),
+                    e.id.to_string(),
+                    e.name.clone(),
+                    e.title.clone().unwrap_or_default(),
+                    e.email.clone().unwrap_or_default(),
+                    e.start_date.clone().unwrap_or_default(),
+                    manager(e).map(Employee::label).unwrap_or_default(),
+                ]
+            })
+            .collect()
+    }
+
+    /// The whole directory in `format`.
+    pub fn export(&self, format: Format) -> String {
+        let header = [
+            "Department",
+            "ID",
+            "Name",
+            "Title",
+            "Email",
+            "Start date",
+            "Manager",
+        ];
+        let mut lines = Vec::new();
+        match format {
+            Format::Csv => {
+                lines.push(header.join(","));
+                for row in self.rows() {
+                    let fields: Vec<String> = row.iter().map(|f| csv_field(f)).collect();
+                    lines.push(fields.join(","));
+                }
+            }
+            Format::Markdown => {
+                lines.push(format!("| {} |", header.join(" | ")));
+                lines.push(format!("|{}", "---|".repeat(header.len())));
+                for row in self.rows() {
+                    let cells: Vec<String> = row.iter().map(|c| markdown_cell(c)).collect();
+                    lines.push(format!("| {} |", cells.join(" | ")));
+                }
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::company::Field;
+
+    fn company() -> Company {
+        let mut company = Company::new();
+        company.add_employee("Ann", "Sales");
+        company.add_employee("Bob", "Engineering");
+        company.add_employee("Amir", "Sales");
+        company.set_manager("Amir", Some("Ann")).unwrap();
+        company
+            .set_field("Ann", Field::Title, Some("Head of Sales, EMEA"))
+            .unwrap();
+        company.add_employee("Cid", "Empty");
+        company.delete_department("Empty").unwrap();
+        company.add_employee("Dee", "Empty");
+        company.remove_employee("Dee", "Empty").unwrap();
+        company
+    }
+
+    #[test]
+    fn counts_heads() {
+        let company = company();
+        assert_eq!(
+            company.headcounts(),
+            vec![("Empty", 0), ("Engineering", 1), ("Sales", 2)]
+        );
+        assert_eq!(
+            company.headcount_report(),
+            "Empty           0\nEngineering     1\nSales           2\nTotal           3"
+        );
+    }
+
+    #[test]
+    fn exports() {
+        let company = company();
+        assert_eq!(
+            company.export(Format::Csv),
+            "Department,ID,Name,Title,Email,Start date,Manager\n\
+             Engineering,2,Bob,,,,\n\
+             Sales,3,Amir,,,,Ann (#1)\n\
+             Sales,1,Ann,\"Head of Sales, EMEA\",,,"
+        );
+        let markdown = company.export(Format::Markdown);
+        assert!(markdown.starts_with("| Department | ID | Name |"));
+        assert!(markdown.ends_with("| Sales | 1 | Ann | Head of Sales, EMEA |  |  |  |"));
+        assert_eq!(Format::parse("MD"), Some(Format::Markdown));
+    }
+
+    #[test]
+    fn escapes_markdown_cells() {
+        assert_eq!(
+            markdown_cell("a|b\\c *d* _e_ `f` [g](h)\nnext"),
+            "a\\|b\\\\c \\*d\\* \\_e\\_ \\`f\\` \\[g\\](h) next"
+        );
+    }
+}
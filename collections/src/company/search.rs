@@ -0,0 +1,177 @@
+//!
+//! Finding people and departments without typing their names exactly:
+//! case is ignored, part of a name is enough, and a near miss like
+//! `Slaes` or `Enginering` still finds `Sales` and `Engineering`.
+//!
+
+use std::fmt;
+
+use super::{Company, Employee, Error};
+
+/// Something `Company::search` found.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Hit<'a> {
+    Employee(&'a Employee),
+    Department(&'a str),
+}
+
+impl Hit<'_> {
+    pub fn name(&self) -> &str {
+        match self {
+            Hit::Employee(employee) => &employee.name,
+            Hit::Department(name) => name,
+        }
+    }
+}
+
+impl fmt::Display for Hit<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Hit::Employee(employee) => write!(f, "{} in {}", employee.label(), employee.department),
+            Hit::Department(name) => write!(f, "department {}", name),
+        }
+    }
+}
+
+impl Company {
+    /// The department called `name`: the one spelt exactly like it, or
+    /// else the only one that differs just in case.
+    pub fn find_department(&self, name: &str) -> Result<&str, Error> {
+        if let Some((department, _)) = self.departments.get_key_value(name) {
+            return Ok(department);
+        }
+        let lower = name.to_lowercase();
+        let found: Vec<&str> = self
+            .departments()
+            .filter(|d| d.to_lowercase() == lower)
+            .collect();
+        match found.as_slice() {
+            [] => Err(Error::NoDepartment(name.to_string())),
+            [department] => Ok(department),
+            _ => Err(Error::AmbiguousDepartment {
+                name: name.to_string(),
+                departments: found.iter().map(|d| d.to_string()).collect(),
+            }),
+        }
+    }
+
+    /// The people and departments whose names match `query`, best match
+    /// first: the same name, then names starting with it, then names
+    /// containing it, then near misses.
+    pub fn search(&self, query: &str) -> Vec<Hit<'_>> {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let departments = self.departments().map(Hit::Department);
+        let employees = self.employees.values().map(Hit::Employee);
+        let mut hits: Vec<(usize, Hit)> = departments
+            .chain(employees)
+            .filter_map(|hit| score(&query, hit.name()).map(|score| (score, hit)))
+            .collect();
+        hits.sort_by(|(a, x), (b, y)| a.cmp(b).then_with(|| x.name().cmp(y.name())));
+        hits.into_iter().map(|(_, hit)| hit).collect()
+    }
+}
+
+/// How well the lower case `query` matches `name`, lower is better; `None`
+/// when it does not match at all.
+fn score(query: &str, name: &str) -> Option<usize> {
+    let name = name.to_lowercase();
+    if name == query {
+        return Some(0);
+    }
+    if name.starts_with(query) {
+        return Some(1);
+    }
+    if name.contains(query) {
+        return Some(2);
+    }
+    // one typo for every three letters typed, against the whole name or
+    // any one word of it
+    let allowed = query.chars().count() / 3;
+    let distance = std::iter::once(name.as_str())
+        .chain(name.split_whitespace())
+        .map(|word| distance(query, word))
+        .min()?;
+    (distance <= allowed).then_some(3 + distance)
+}
+
+/// How many letters have to be inserted, deleted, replaced or swapped
+/// with their neighbour to turn `a` into `b`.
+fn distance(a: &str, b: &str) -> usize {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    // rows i - 2, i - 1 and i of the usual table
+    let mut before: Vec<usize> = vec![0; b.len() + 1];
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        let mut current = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            current[j] = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(before[j - 2] + 1);
+            }
+        }
+        before = std::mem::replace(&mut previous, current);
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measures_typos() {
+        assert_eq!(distance("sales", "sales"), 0);
+        assert_eq!(distance("slaes", "sales"), 1);
+        assert_eq!(distance("enginering", "engineering"), 1);
+        assert_eq!(distance("", "bob"), 3);
+        assert_eq!(distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn finds_people_and_departments() {
+        let mut company = Company::new();
+        company.add_employee("Mary Jane", "Sales");
+        company.add_employee("Salim", "Engineering");
+        company.add_employee("Marty", "Support");
+        assert_eq!(company.find_department("sALES"), Ok("Sales"));
+        assert_eq!(
+            company.find_department("Sale"),
+            Err(Error::NoDepartment("Sale".to_string()))
+        );
+        company.add_employee("Bob", "SALES");
+        assert_eq!(company.find_department("SALES"), Ok("SALES"));
+        assert_eq!(
+            company.find_department("sales"),
+            Err(Error::AmbiguousDepartment {
+                name: "sales".to_string(),
+                departments: vec!["SALES".to_string(), "Sales".to_string()],
+            })
+        );
+        company.delete_department("SALES").unwrap();
+
+        let names = |query| -> Vec<String> {
+            company
+                .search(query)
+                .iter()
+                .map(|hit| hit.to_string())
+                .collect()
+        };
+        assert_eq!(
+            names("sal"),
+            vec!["department Sales", "Salim (#2) in Engineering"]
+        );
+        assert_eq!(names("jane"), vec!["Mary Jane (#1) in Sales"]);
+        assert_eq!(names("Enginering"), vec!["department Engineering"]);
+        assert_eq!(
+            names("mary"),
+            vec!["Mary Jane (#1) in Sales", "Marty (#3) in Support"]
+        );
+        assert!(names("xyz").is_empty());
+    }
+}
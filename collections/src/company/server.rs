@@ -0,0 +1,499 @@
+//!
+//! `collections serve [--port N]`
+//!
+//! Serves the saved directory as JSON over HTTP on 127.0.0.1 (port 7878
+//! unless given), one thread per connection around a shared `Session`,
+//! so changes are logged, undoable and saved just like REPL commands.
+//!
+//! ```text
+//! GET    /departments                      ["Engineering", "Sales"]
+//! GET    /departments/DEPT/employees       the employees of DEPT
+//! GET    /employees                        every employee
+//! GET    /employees/WHO                    one employee
+//! POST   /employees                        {"name": "Ann", "department": "Sales"}
+//! PATCH  /employees/WHO                    {"department": "Support"} moves WHO
+//! DELETE /departments/DEPT/employees/WHO   removes WHO
+//! ```
+//!
+//! WHO is a name or `#ID` (written `%237` in a URL). Errors come back as
+//! `{"error": "..."}` with 404 for a missing department or employee, 409
+//! for an ambiguous name, 400 for a bad request, 405 with an `Allow`
+//! header for a method a path does not support, and 431 for headers that
+//! are too long or too many.
+//!
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use super::command::quote;
+use super::session::{self, Session};
+use super::{Employee, Error, store};
+use crate::cli;
+
+const DEFAULT_PORT: u16 = 7878;
+
+/// Requests with a larger body are refused.
+const MAX_BODY: usize = 64 * 1024;
+
+/// The longest request line or header line read, in bytes.
+const MAX_LINE: usize = 8 * 1024;
+
+/// The most header lines read.
+const MAX_HEADERS: usize = 100;
+
+#[derive(Debug, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    /// Headers besides the content type, length and `Connection: close`.
+    pub headers: Vec<(&'static str, String)>,
+    pub body: Value,
+}
+
+impl Response {
+    fn ok(body: Value) -> Self {
+        Response {
+            status: 200,
+            headers: Vec::new(),
+            body,
+        }
+    }
+
+    fn error(status: u16, msg: impl ToString) -> Self {
+        Response {
+            status,
+            headers: Vec::new(),
+            body: json!({ "error": msg.to_string() }),
+        }
+    }
+}
+
+impl From<session::Error> for Response {
+    fn from(err: session::Error) -> Self {
+        let status = match &err {
+            session::Error::Company(err) => match err {
+                Error::NoDepartment(_) | Error::NoEmployee { .. } | Error::NoSuchEmployee(_) => 404,
+                Error::Ambiguous { .. }
+                | Error::AmbiguousDepartment { .. }
+                | Error::DepartmentExists(_)
+                | Error::Cycle(_) => 409,
+                Error::InvalidValue { .. } => 400,
+            },
+            session::Error::Store(_) => 500,
+        };
+        Response::error(status, err)
+    }
+}
+
+impl From<Error> for Response {
+    fn from(err: Error) -> Self {
+        session::Error::Company(err).into()
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        413 => "Payload Too Large",
+        431 => "Request Header Fields Too Large",
+        _ => "Internal Server Error",
+    }
+}
+
+#[derive(Deserialize)]
+struct NewEmployee {
+    name: String,
+    department: String,
+}
+
+#[derive(Deserialize)]
+struct Transfer {
+    department: String,
+}
+
+/// Undo `%XX` escapes in one path segment.
+fn percent_decode(segment: &str) -> Option<String> {
+    let mut bytes = Vec::new();
+    let mut rest = segment.bytes();
+    while let Some(b) = rest.next() {
+        if b == b'%' {
+            let hex = [rest.next()?, rest.next()?];
+            bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            bytes.push(b);
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+fn parse_body<T: for<'de> Deserialize<'de>>(body: &[u8]) -> Result<T, Response> {
+    serde_json::from_slice(body).map_err(|err| Response::error(400, err))
+}
+
+fn employee(employee: &Employee) -> Value {
+    serde_json::to_value(employee).expect("an employee always serializes")
+}
+
+fn employees(employees: &[&Employee]) -> Value {
+    Value::Array(employees.iter().map(|e| employee(e)).collect())
+}
+
+/// Answer one request.
+pub fn route(session: &Mutex<Session>, method: &str, path: &str, body: &[u8]) -> Response {
+    let path = path.split('?').next().unwrap_or_default();
+    let segments: Option<Vec<String>> = path
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .map(percent_decode)
+        .collect();
+    let Some(segments) = segments else {
+        return Response::error(400, "the path is not valid UTF-8");
+    };
+    let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+    let mut session = session.lock().unwrap_or_else(PoisonError::into_inner);
+    let result = match (method, segments.as_slice()) {
+        ("GET", ["departments"]) => {
+            let names: Vec<&str> = session.company().departments().collect();
+            Ok(Response::ok(json!(names)))
+        }
+        ("GET", ["departments", department, "employees"]) => {
+            match session.company().get_employees(department) {
+                Some(list) => Ok(Response::ok(employees(&list))),
+                None => Err(Error::NoDepartment(department.to_string()).into()),
+            }
+        }
+        ("GET", ["employees"]) => {
+            let company = session.company();
+            let list: Vec<&Employee> = company.get_all_employees().flat_map(|(_, e)| e).collect();
+            Ok(Response::ok(employees(&list)))
+        }
+        ("GET", ["employees", who]) => session
+            .company()
+            .find(who)
+            .map(|e| Response::ok(employee(e)))
+            .map_err(Response::from),
+        ("POST", ["employees"]) => parse_body(body).and_then(|new: NewEmployee| {
+            let (name, department) = (new.name.trim(), new.department.trim());
+            if name.is_empty() || department.is_empty() {
+                return Err(Response::error(
+                    400,
+                    "name and department must not be empty",
+                ));
+            }
+            let command = format!("add {} to {}", quote(name), quote(department));
+            let id = session.change(&command, |company| {
+                Ok(company.add_employee(name, department))
+            })?;
+            let added = session.company().find(&format!("#{}", id)).map(employee);
+            Ok(Response {
+                status: 201,
+                headers: Vec::new(),
+                body: added.expect("the employee was just added"),
+            })
+        }),
+        ("PATCH", ["employees", who]) => parse_body(body).and_then(|transfer: Transfer| {
+            let to = transfer.department.trim();
+            if to.is_empty() {
+                return Err(Response::error(400, "department must not be empty"));
+            }
+            let found = session.company().find(who).map_err(Response::from)?;
+            let (id, from) = (format!("#{}", found.id), found.department.clone());
+            let command = format!("move {} from {} to {}", id, quote(&from), quote(to));
+            session.change(&command, |company| company.move_employee(&id, &from, to))?;
+            let moved = session.company().find(&id).map(employee);
+            Ok(Response::ok(moved.expect("the employee was just moved")))
+        }),
+        ("DELETE", ["departments", department, "employees", who]) => {
+            let command = format!("remove {} from {}", quote(who), quote(department));
+            session
+                .change(&command, |company| company.remove_employee(who, department))
+                .map(|removed| Response::ok(employee(&removed)))
+                .map_err(Response::from)
+        }
+        (_, segments) => match allowed_methods(segments) {
+            Some(allow) => {
+                let mut response = Response::error(405, format!("{} is not allowed here", method));
+                response.headers.push(("Allow", allow.to_string()));
+                Err(response)
+            }
+            None => Err(Response::error(404, format!("no such resource {}", path))),
+        },
+    };
+    result.unwrap_or_else(|response| response)
+}
+
+/// The methods `route` answers for the resource at `segments`, as an
+/// `Allow` header lists them; `None` when there is no such resource.
+fn allowed_methods(segments: &[&str]) -> Option<&'static str> {
+    match segments {
+        ["departments"] | ["departments", _, "employees"] => Some("GET"),
+        ["departments", _, "employees", _] => Some("DELETE"),
+        ["employees"] => Some("GET, POST"),
+        ["employees", _] => Some("GET, PATCH"),
+        _ => None,
+    }
+}
+
+struct Request {
+    method: String,
+    path: String,
+    body: Vec<u8>,
+}
+
+/// Read one line of at most `MAX_LINE` bytes; `None` when it is longer.
+fn read_line(reader: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut line = String::new();
+    reader.take(MAX_LINE as u64 + 1).read_line(&mut line)?;
+    Ok((line.len() <= MAX_LINE).then_some(line))
+}
+
+/// Read the request line, the headers and the body. `Err` holds the
+/// response for a request that is malformed or too large.
+fn read_request(reader: &mut impl BufRead) -> io::Result<Result<Request, Response>> {
+    let Some(request_line) = read_line(reader)? else {
+        return Ok(Err(Response::error(400, "the request line is too long")));
+    };
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    if method.is_empty() || !path.starts_with('/') {
+        return Ok(Err(Response::error(
+            400,
+            "expected a request line like `GET /departments HTTP/1.1`",
+        )));
+    }
+    let mut length = 0;
+    let mut count = 0;
+    loop {
+        let Some(header) = read_line(reader)? else {
+            return Ok(Err(Response::error(431, "a header line is too long")));
+        };
+        if header.trim().is_empty() {
+            break;
+        }
+        count += 1;
+        if count > MAX_HEADERS {
+            return Ok(Err(Response::error(
+                431,
+                format!("there may be at most {} headers", MAX_HEADERS),
+            )));
+        }
+        if let Some((name, value)) = header.split_once(':')
+            && name.trim().eq_ignore_ascii_case("content-length")
+        {
+            let Ok(value) = value.trim().parse() else {
+                return Ok(Err(Response::error(400, "Content-Length is not a number")));
+            };
+            length = value;
+        }
+    }
+    if length > MAX_BODY {
+        return Ok(Err(Response::error(
+            413,
+            format!("the body may be at most {} bytes", MAX_BODY),
+        )));
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    Ok(Ok(Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        body,
+    }))
+}
+
+/// Read one request from `stream`, answer it and close the connection.
+fn handle(stream: TcpStream, session: &Mutex<Session>) -> io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut reader = BufReader::new(&stream);
+    let response = match read_request(&mut reader)? {
+        Ok(request) => route(session, &request.method, &request.path, &request.body),
+        Err(response) => response,
+    };
+    let body = response.body.to_string();
+    let mut stream = &stream;
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\n",
+        response.status,
+        reason(response.status)
+    )?;
+    for (name, value) in &response.headers {
+        write!(stream, "{}: {}\r\n", name, value)?;
+    }
+    write!(
+        stream,
+        "Content-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+/// Answer connections on `listener` until the process ends.
+pub fn serve(listener: TcpListener, session: Arc<Mutex<Session>>) {
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else { continue };
+        let session = Arc::clone(&session);
+        thread::spawn(move || {
+            if let Err(err) = handle(stream, &session) {
+                eprintln!("serve: {}", err);
+            }
+        });
+    }
+}
+
+pub fn parse_args(args: &[String]) -> Result<u16, String> {
+    match args {
+        [] => Ok(DEFAULT_PORT),
+        [flag, port] if flag == "--port" => port
+            .parse()
+            .map_err(|_| format!("`{}` is not a port", port)),
+        [flag] if flag == "--port" => Err("--port needs a value".to_string()),
+        [other, ..] => Err(format!("unexpected argument {}", other)),
+    }
+}
+
+/// Run the `serve` command with the arguments after `serve`. Returns the
+/// process exit status.
+pub fn run(args: &[String]) -> i32 {
+    cli::exit_status("serve", try_run(args).map(|()| 0))
+}
+
+fn try_run(args: &[String]) -> Result<(), String> {
+    let port = parse_args(args)?;
+    let session = Session::open(store::default_path()).map_err(|e| e.to_string())?;
+    let address = ("127.0.0.1", port);
+    let listener = TcpListener::bind(address).map_err(|e| format!("port {}: {}", port, e))?;
+    println!("Serving the company directory on http://127.0.0.1:{}", port);
+    serve(listener, Arc::new(Mutex::new(session)));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::testing::temp_dir;
+    use std::fs;
+
+    fn session(name: &str) -> (std::path::PathBuf, Mutex<Session>) {
+        let dir = temp_dir(&format!("server-{}", name));
+        let session = Session::open(dir.join("company.json")).unwrap();
+        (dir, Mutex::new(session))
+    }
+
+    #[test]
+    fn routes_requests() {
+        let (dir, session) = session("route");
+        let add = |body: &str| route(&session, "POST", "/employees", body.as_bytes());
+        let created = add(r#"{"name": "Mary Jane", "department": "Sales"}"#);
+        assert_eq!(created.status, 201);
+        assert_eq!(created.body["id"], 1);
+        assert_eq!(add(r#"{"name": "Bob"}"#).status, 400);
+        add(r#"{"name": "Bob", "department": "Sales"}"#);
+
+        let get = |path: &str| route(&session, "GET", path, b"");
+        assert_eq!(get("/departments").body, json!(["Sales"]));
+        assert_eq!(get("/departments/Sales/employees").body[0]["name"], "Bob");
+        assert_eq!(get("/departments/Support/employees").status, 404);
+        assert_eq!(get("/employees/Mary%20Jane").body["id"], 1);
+        assert_eq!(get("/nowhere").status, 404);
+        let refused = route(&session, "PUT", "/employees", b"");
+        assert_eq!(refused.status, 405);
+        assert_eq!(refused.headers, vec![("Allow", "GET, POST".to_string())]);
+        let refused = route(&session, "GET", "/departments/Sales/employees/Bob", b"");
+        assert_eq!(refused.headers, vec![("Allow", "DELETE".to_string())]);
+
+        let moved = route(
+            &session,
+            "PATCH",
+            "/employees/%231",
+            br#"{"department": "Support"}"#,
+        );
+        assert_eq!(moved.body["department"], "Support");
+        let removed = route(&session, "DELETE", "/departments/Sales/employees/Bob", b"");
+        assert_eq!(removed.body["name"], "Bob");
+        let again = route(&session, "DELETE", "/departments/Sales/employees/Bob", b"");
+        assert_eq!(again.status, 404);
+        // names with keywords in them are quoted in the log, so the logged
+        // command parses back to the same change
+        add(r#"{"name": "Cid", "department": "Back to School"}"#);
+        let log = fs::read_to_string(dir.join("company.log")).unwrap();
+        let logged: serde_json::Value = serde_json::from_str(log.lines().last().unwrap()).unwrap();
+        assert_eq!(
+            crate::company::parse_command(logged["command"].as_str().unwrap()),
+            Ok(crate::company::Command::Add {
+                name: "Cid".to_string(),
+                department: "Back to School".to_string(),
+            })
+        );
+        route(
+            &session,
+            "DELETE",
+            "/departments/Back%20to%20School/employees/Cid",
+            b"",
+        );
+        // the changes went through the session, so they can be undone
+        let mut session = session.into_inner().unwrap();
+        assert_eq!(
+            session.run("undo"),
+            Ok("Undid: remove Cid from \"Back to School\"".to_string())
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn answers_over_tcp() {
+        let (dir, session) = session("tcp");
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || serve(listener, Arc::new(session)));
+
+        let request = |text: &str| {
+            let mut stream = TcpStream::connect(address).unwrap();
+            stream.write_all(text.as_bytes()).unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        };
+        let body = r#"{"name": "Ann", "department": "Sales"}"#;
+        let response = request(&format!(
+            "POST /employees HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        ));
+        assert!(response.starts_with("HTTP/1.1 201 Created\r\n"));
+        let response = request("GET /departments/Support/employees HTTP/1.1\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 404 Not Found\r\n"));
+        assert!(response.ends_with(r#"{"error":"There is no department Support"}"#));
+        let long = "x".repeat(MAX_LINE + 1);
+        let response = request(&format!(
+            "GET /departments HTTP/1.1\r\nX-Long: {}\r\n\r\n",
+            long
+        ));
+        assert!(response.starts_with("HTTP/1.1 431 "));
+        let headers = "X-A: 1\r\n".repeat(MAX_HEADERS + 1);
+        let response = request(&format!("GET /departments HTTP/1.1\r\n{}\r\n", headers));
+        assert!(response.starts_with("HTTP/1.1 431 "));
+        let response = request(&format!("GET /{} HTTP/1.1\r\n\r\n", long));
+        assert!(response.starts_with("HTTP/1.1 400 "));
+        let response = request(&format!(
+            "POST /employees HTTP/1.1\r\nContent-Length: lots\r\n\r\n{}",
+            body
+        ));
+        assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+        assert!(response.ends_with(r#"{"error":"Content-Length is not a number"}"#));
+        let response = request("DELETE /employees HTTP/1.1\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 405 Method Not Allowed\r\nAllow: GET, POST\r\n"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,377 @@
+//!
+//! One REPL session: the directory, the file it is saved to and the
+//! undo history. `Session::run` takes a line as typed and returns what to
+//! print, so the REPL itself only reads and prints.
+//!
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use super::command::HELP;
+use super::history::{self, History};
+use super::{Command, Company, Employee, Hit, parse_command, store};
+
+/// Why a change made through a session did not go through.
+#[derive(Debug)]
+pub enum Error {
+    /// The change itself was refused; the directory is unchanged.
+    Company(super::Error),
+    /// The change could not be logged or saved.
+    Store(store::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Company(err) => err.fmt(f),
+            Error::Store(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+pub struct Session {
+    company: Company,
+    path: PathBuf,
+    history: History,
+}
+
+impl Session {
+    /// Load the directory at `path` and start logging next to it.
+    pub fn open(path: PathBuf) -> Result<Self, store::Error> {
+        let company = store::load(&path)?;
+        let history = History::new(history::log_path(&path));
+        history.import(&company)?;
+        Ok(Session {
+            company,
+            path,
+            history,
+        })
+    }
+
+    pub fn company(&self) -> &Company {
+        &self.company
+    }
+
+    /// Parse and run one line. `Err` holds the message for a line that
+    /// could not be run; the directory is unchanged then.
+    pub fn run(&mut self, input: &str) -> Result<String, String> {
+        let command = parse_command(input).map_err(|err| err.render(input))?;
+        self.run_command(input, command)
+    }
+
+    /// Run `command`, already parsed from the line `input`, which is what
+    /// undo and the audit log record for it.
+    pub fn run_command(&mut self, input: &str, command: Command) -> Result<String, String> {
+        let (output, undo) = match command {
+            Command::Undo => match self.history.undo(&mut self.company) {
+                Ok(Some(command)) => (format!("Undid: {}", command), false),
+                Ok(None) => return Ok("Nothing to undo".to_string()),
+                Err(err) => return Err(err.to_string()),
+            },
+            Command::Redo => match self.history.redo(&mut self.company) {
+                Ok(Some(command)) => (format!("Redid: {}", command), true),
+                Ok(None) => return Ok("Nothing to redo".to_string()),
+                Err(err) => return Err(err.to_string()),
+            },
+            Command::History => return self.recent(10).map_err(|err| err.to_string()),
+            Command::Help => return Ok(HELP.to_string()),
+            command if command.changes_directory() => {
+                return self
+                    .change(input, |company| execute(company, command))
+                    .map_err(|err| err.to_string());
+            }
+            command => {
+                return execute(&mut self.company, command).map_err(|err| err.to_string());
+            }
+        };
+        if let Err(err) = store::save(&self.company, &self.path) {
+            // go back to what is saved; the log gets the opposite entry
+            let _ = if undo {
+                self.history.undo(&mut self.company)
+            } else {
+                self.history.redo(&mut self.company)
+            };
+            return Err(err.to_string());
+        }
+        Ok(output)
+    }
+
+    /// Make a change with `f`, record it as `command` for undo and the
+    /// audit log, and save the directory. A change that could not be saved
+    /// is taken back, and one that changed nothing is not recorded.
+    pub fn change<T>(
+        &mut self,
+        command: &str,
+        f: impl FnOnce(&mut Company) -> Result<T, super::Error>,
+    ) -> Result<T, Error> {
+        let before = self.company.clone();
+        let value = match f(&mut self.company) {
+            Ok(value) => value,
+            Err(err) => {
+                self.company = before;
+                return Err(Error::Company(err));
+            }
+        };
+        let changes = history::diff(&before, &self.company);
+        if changes.is_empty() {
+            return Ok(value);
+        }
+        if let Err(err) = self.history.record(command, changes) {
+            self.company = before;
+            return Err(Error::Store(err));
+        }
+        if let Err(err) = store::save(&self.company, &self.path) {
+            // the log gets an undo entry, so replaying it still gives
+            // what is saved
+            let _ = self.history.retract(&mut self.company);
+            self.company = before;
+            return Err(Error::Store(err));
+        }
+        Ok(value)
+    }
+
+    /// The last `count` entries of the audit log, oldest first.
+    fn recent(&self, count: usize) -> Result<String, store::Error> {
+        let entries = history::read_log(self.history.log())?;
+        if entries.is_empty() {
+            return Ok("Nothing has been changed yet".to_string());
+        }
+        let lines: Vec<String> = entries[entries.len().saturating_sub(count)..]
+            .iter()
+            .map(|entry| {
+                format!(
+                    "{}  {:<8} {:<6} {}",
+                    format_time(entry.time),
+                    entry.user,
+                    format!("{:?}", entry.action).to_lowercase(),
+                    entry.command
+                )
+            })
+            .collect();
+        Ok(lines.join("\n"))
+    }
+}
+
+/// Rebuild the directory from the audit log of the company file at
+/// `path`. With `write` the result replaces the company file.
+pub fn replay(path: &Path, write: bool) -> Result<Company, store::Error> {
+    let entries = history::read_log(&history::log_path(path))?;
+    let company = history::replay(&entries);
+    if write {
+        store::save(&company, path)?;
+    }
+    Ok(company)
+}
+
+/// `seconds` since the Unix epoch as a UTC date and time.
+fn format_time(seconds: u64) -> String {
+    let days = (seconds / 86_400) as i64;
+    let time = seconds % 86_400;
+    // Howard Hinnant's civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        time / 3_600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+/// Run one command on `company` and return what to print.
+fn execute(company: &mut Company, command: Command) -> Result<String, super::Error> {
+    let list = |employees: &[&Employee]| {
+        if employees.is_empty() {
+            return "nobody".to_string();
+        }
+        let labels: Vec<String> = employees.iter().map(|e| e.label()).collect();
+        labels.join(", ")
+    };
+    Ok(match command {
+        Command::Add { name, department } => {
+            let id = company.add_employee(&name, &department);
+            format!("Added {} (#{}) to {}", name, id, department)
+        }
+        Command::Remove { who, department } => {
+            let employee = company.remove_employee(&who, &department)?;
+            format!("Removed {} from {}", employee.label(), department)
+        }
+        Command::Move { who, from, to } => {
+            let id = company.move_employee(&who, &from, &to)?;
+            format!("Moved {} (#{}) from {} to {}", who, id, from, to)
+        }
+        Command::RenameDepartment { from, to } => {
+            company.rename_department(&from, &to)?;
+            format!("Renamed {} to {}", from, to)
+        }
+        Command::DeleteDepartment { department } => {
+            let employees = company.delete_department(&department)?;
+            let count = employees.len();
+            format!("Deleted {} and its {} employees", department, count)
+        }
+        Command::Set { field, who, value } => {
+            let id = company.set_field(&who, field, value.as_deref())?;
+            match value {
+                Some(value) => format!("Set {} of #{} to {}", field, id, value),
+                None => format!("Cleared {} of #{}", field, id),
+            }
+        }
+        Command::Show { who } => {
+            let employee = company.find(&who)?;
+            let mut output = employee.to_string();
+            if let Some(manager) = employee.manager {
+                let manager = company.find(&format!("#{}", manager))?;
+                output.push_str(&format!("\nmanager    : {}", manager.label()));
+            }
+            let reports = company.reports(employee.id);
+            if !reports.is_empty() {
+                output.push_str(&format!("\nreports    : {}", list(&reports)));
+            }
+            output
+        }
+        Command::SetManager { who, manager } => {
+            let id = company.set_manager(&who, manager.as_deref())?;
+            match manager {
+                Some(manager) => format!("#{} now reports to {}", id, manager),
+                None => format!("#{} has no manager now", id),
+            }
+        }
+        Command::Tree { department } => company.reporting_tree(&department)?,
+        Command::Chain { who } => {
+            let chain = company.chain_of_command(&who)?;
+            let labels: Vec<String> = chain.iter().map(|e| e.label()).collect();
+            labels.join(" → ")
+        }
+        Command::ListDepartment { department } => match company.find_department(&department) {
+            Ok(found) => {
+                let employees = company.get_employees(found).unwrap_or_default();
+                format!("Employees in {}: {}", found, list(&employees))
+            }
+            Err(err @ super::Error::AmbiguousDepartment { .. }) => return Err(err),
+            Err(_) => {
+                let close = company
+                    .search(&department)
+                    .into_iter()
+                    .find_map(|hit| match hit {
+                        Hit::Department(name) => Some(name),
+                        Hit::Employee(_) => None,
+                    });
+                match close {
+                    Some(name) => format!(
+                        "No employees found in {}; did you mean {}?",
+                        department, name
+                    ),
+                    None => format!("No employees found in {}", department),
+                }
+            }
+        },
+        Command::ListAll => {
+            let mut output = String::from("All employees:");
+            for (department, employees) in company.get_all_employees() {
+                output.push_str(&format!("\n{} : {}", department, list(&employees)));
+            }
+            output
+        }
+        Command::Find { query } => {
+            let hits: Vec<String> = company.search(&query).iter().map(Hit::to_string).collect();
+            if hits.is_empty() {
+                format!("Nothing matches {}", query)
+            } else {
+                hits.join("\n")
+            }
+        }
+        Command::Headcount => company.headcount_report(),
+        Command::Export { format } => company.export(format),
+        Command::Undo | Command::Redo | Command::History | Command::Help => {
+            unreachable!("handled by Session::run")
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::testing::temp_dir;
+    use std::fs;
+
+    #[test]
+    fn undo_redo_through_the_session() {
+        let dir = temp_dir("session");
+        let path = dir.join("company.json");
+        let mut session = Session::open(path.clone()).unwrap();
+        session.run("add Ann to Sales").unwrap();
+        session.run("add Bob to Sales").unwrap();
+        assert!(session.run("remove Cid from Sales").is_err());
+        assert_eq!(session.run("undo").unwrap(), "Undid: add Bob to Sales");
+        assert_eq!(store::load(&path).unwrap().departments().count(), 1);
+        assert!(
+            session
+                .run("list employees in Sales")
+                .unwrap()
+                .ends_with("Ann (#1)")
+        );
+        assert_eq!(session.run("redo").unwrap(), "Redid: add Bob to Sales");
+        assert_eq!(session.run("redo").unwrap(), "Nothing to redo");
+        assert_eq!(session.run("history").unwrap().lines().count(), 4);
+
+        let replayed = replay(&path, false).unwrap();
+        assert_eq!(replayed, store::load(&path).unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn history_of_a_new_directory() {
+        let dir = temp_dir("fresh");
+        let mut session = Session::open(dir.join("company.json")).unwrap();
+        assert_eq!(
+            session.run("history").unwrap(),
+            "Nothing has been changed yet"
+        );
+        session.run("add Ann to Sales").unwrap();
+        assert!(
+            session
+                .run("history")
+                .unwrap()
+                .ends_with("add Ann to Sales")
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn takes_back_what_cannot_be_saved() {
+        let dir = temp_dir("unsaved");
+        let path = dir.join("company.json");
+        let mut session = Session::open(path.clone()).unwrap();
+        session.run("add Ann to Sales").unwrap();
+        session.run("rename department Sales to Sales").unwrap();
+        assert_eq!(session.run("history").unwrap().lines().count(), 1);
+
+        // a directory where the company file goes cannot be replaced
+        fs::remove_file(&path).unwrap();
+        fs::create_dir(&path).unwrap();
+        assert!(session.run("add Bob to Sales").is_err());
+        assert!(session.run("undo").is_err());
+        assert_eq!(session.company().get_employees("Sales").unwrap().len(), 1);
+        assert_eq!(session.run("redo").unwrap(), "Nothing to redo");
+        assert_eq!(&replay(&path, false).unwrap(), session.company());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn formats_times() {
+        assert_eq!(format_time(0), "1970-01-01 00:00:00");
+        assert_eq!(format_time(1_709_210_096), "2024-02-29 12:34:56");
+    }
+}
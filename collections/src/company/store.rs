@@ -0,0 +1,286 @@
+//!
+//! Saving the directory between runs. The file is JSON:
+//!
+//! ```json
+//! {
+//!   "version": 2,
+//!   "next_id": 3,
+//!   "departments": ["Marketing", "Sales"],
+//!   "employees": [
+//!     { "id": 1, "name": "Amir", "department": "Sales", "title": "Manager" },
+//!     { "id": 2, "name": "Sally", "department": "Sales" }
+//!   ]
+//! }
+//! ```
+//!
+//! Version 1 files, which only mapped departments to names, are still
+//! read; their employees get IDs department by department and the file is
+//! written as version 2 on the next save.
+//!
+//! It is written to a temporary file next to the real one first and then
+//! renamed over it, so a crash while saving never leaves half a file. A
+//! missing file is an empty company; a file that cannot be read is an
+//! error, never silently replaced.
+//!
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{Company, Employee, Roster};
+
+/// The format version written by this build.
+pub const VERSION: u64 = 2;
+
+#[derive(Debug)]
+pub enum Error {
+    Io { path: PathBuf, source: io::Error },
+    Corrupt { path: PathBuf, msg: String },
+    Version { path: PathBuf, found: u64 },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            Error::Corrupt { path, msg } => write!(
+                f,
+                "{}: the file is corrupt ({}); fix it or move it away",
+                path.display(),
+                msg
+            ),
+            Error::Version { path, found } => write!(
+                f,
+                "{}: the company file has version {}, this build reads up to version {}",
+                path.display(),
+                found,
+                VERSION
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Stored {
+    version: u64,
+    next_id: u32,
+    departments: BTreeSet<String>,
+    employees: Vec<Employee>,
+}
+
+#[derive(Deserialize)]
+struct StoredV1 {
+    departments: BTreeMap<String, Vec<String>>,
+}
+
+/// Where the directory is kept: `$COMPANY_FILE` when set, otherwise
+/// `collections/company.json` in `$XDG_DATA_HOME` or `~/.local/share`.
+pub fn default_path() -> PathBuf {
+    if let Some(path) = env::var_os("COMPANY_FILE") {
+        return PathBuf::from(path);
+    }
+    let data = env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".local/share")))
+        .unwrap_or_default();
+    data.join("collections").join("company.json")
+}
+
+/// Read the directory at `path`; an empty one when there is no file yet.
+pub fn load(path: &Path) -> Result<Company, Error> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Company::new()),
+        Err(source) => {
+            return Err(Error::Io {
+                path: path.to_path_buf(),
+                source,
+            });
+        }
+    };
+    let corrupt = |msg: String| Error::Corrupt {
+        path: path.to_path_buf(),
+        msg,
+    };
+    // look at the version before the rest, so a newer file is reported as
+    // such instead of as corrupt
+    let value: Value = serde_json::from_str(&text).map_err(|e| corrupt(e.to_string()))?;
+    let version = value
+        .get("version")
+        .and_then(Value::as_u64)
+        .ok_or_else(|| corrupt("no version number".to_string()))?;
+    match version {
+        1 => {
+            let stored: StoredV1 =
+                serde_json::from_value(value).map_err(|e| corrupt(e.to_string()))?;
+            let mut company = Company::new();
+            for (department, names) in stored.departments {
+                company.departments.entry(department.clone()).or_default();
+                for name in names {
+                    company.add_employee(&name, &department);
+                }
+            }
+            Ok(company)
+        }
+        VERSION => {
+            let stored: Stored =
+                serde_json::from_value(value).map_err(|e| corrupt(e.to_string()))?;
+            let mut company = Company::new();
+            for department in stored.departments {
+                company.departments.insert(department, Roster::new());
+            }
+            for employee in stored.employees {
+                if company.employees.contains_key(&employee.id) {
+                    return Err(corrupt(format!(
+                        "employee ID {} is used twice",
+                        employee.id
+                    )));
+                }
+                company.insert(employee);
+            }
+            for employee in company.employees.values() {
+                // a chain longer than the whole company must go round in a loop
+                let mut manager = employee.manager;
+                for _ in 0..=company.employees.len() {
+                    let Some(id) = manager else { break };
+                    let Some(next) = company.employees.get(&id) else {
+                        return Err(corrupt(format!(
+                            "employee {} reports to missing employee {}",
+                            employee.id, id
+                        )));
+                    };
+                    manager = next.manager;
+                }
+                if manager.is_some() {
+                    return Err(corrupt(format!(
+                        "employee {} is in a reporting loop",
+                        employee.id
+                    )));
+                }
+            }
+            let last = company.employees.keys().next_back().copied().unwrap_or(0);
+            company.next_id = stored.next_id.max(last + 1);
+            Ok(company)
+        }
+        _ => Err(Error::Version {
+            path: path.to_path_buf(),
+            found: version,
+        }),
+    }
+}
+
+/// Write `company` to `path`, replacing the file in one step.
+pub fn save(company: &Company, path: &Path) -> Result<(), Error> {
+    let stored = Stored {
+        version: VERSION,
+        next_id: company.next_id,
+        departments: company.departments.keys().cloned().collect(),
+        employees: company.employees.values().cloned().collect(),
+    };
+    let io_error = |source| Error::Io {
+        path: path.to_path_buf(),
+        source,
+    };
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).map_err(io_error)?;
+    }
+    let json = serde_json::to_string_pretty(&stored).expect("a company always serializes");
+    let mut file = File::create(&tmp).map_err(io_error)?;
+    file.write_all(json.as_bytes())
+        .and_then(|()| file.write_all(b"\n"))
+        .and_then(|()| file.sync_all())
+        .map_err(io_error)?;
+    fs::rename(&tmp, path).map_err(io_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::company::Field;
+    use std::process;
+
+    fn temp_file(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("collections-store-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir.join(name)
+    }
+
+    #[test]
+    fn saves_and_loads() {
+        let path = temp_file("round-trip.json");
+        let mut company = Company::new();
+        company.add_employee("Sally", "Engineering");
+        company.add_employee("Amir", "Sales");
+        save(&company, &path).unwrap();
+        company
+            .set_field("Amir", Field::Title, Some("Manager"))
+            .unwrap();
+        company.add_employee("Bob", "Marketing");
+        company.delete_department("Marketing").unwrap();
+        company.add_employee("Carol", "Support");
+        company.remove_employee("Carol", "Support").unwrap();
+        save(&company, &path).unwrap();
+        assert_eq!(load(&path).unwrap(), company);
+        assert!(!temp_file("round-trip.json.tmp").exists());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn reads_version_1_files() {
+        let path = temp_file("v1.json");
+        fs::write(
+            &path,
+            r#"{"version": 1, "departments": {"Sales": ["Sally", "Amir"], "Empty": []}}"#,
+        )
+        .unwrap();
+        let company = load(&path).unwrap();
+        assert_eq!(company.find("Sally").unwrap().id, 1);
+        assert_eq!(company.find("Amir").unwrap().department, "Sales");
+        assert_eq!(company.get_employees("Empty").unwrap().len(), 0);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn missing_file_is_an_empty_company() {
+        let company = load(&temp_file("missing.json")).unwrap();
+        assert!(company.departments().next().is_none());
+    }
+
+    #[test]
+    fn corrupt_and_newer_files_are_errors() {
+        let path = temp_file("corrupt.json");
+        fs::write(&path, "{\"version\": 1, \"departments\": {\"Sales\": [").unwrap();
+        assert!(matches!(load(&path), Err(Error::Corrupt { .. })));
+        fs::write(&path, "{\"departments\": {}}").unwrap();
+        assert!(matches!(load(&path), Err(Error::Corrupt { .. })));
+        fs::write(
+            &path,
+            r#"{"version": 2, "next_id": 3, "departments": [], "employees": [
+                {"id": 1, "name": "Ann", "department": "X", "manager": 2},
+                {"id": 2, "name": "Bob", "department": "X", "manager": 1}]}"#,
+        )
+        .unwrap();
+        assert!(matches!(load(&path), Err(Error::Corrupt { .. })));
+        fs::write(&path, "{\"version\": 3, \"people\": []}").unwrap();
+        assert!(matches!(load(&path), Err(Error::Version { found: 3, .. })));
+        fs::remove_file(&path).unwrap();
+    }
+}
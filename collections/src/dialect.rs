@@ -0,0 +1,271 @@
+//!
+//! Language games that rewrite text word by word. Each game is a
+//! `Dialect`; `by_name` picks one from its name and `transform` runs it
+//! over a whole document, one line at a time, so the input never has to
+//! be in memory at once.
+//!
+//! - `pig-latin`: “string” → “ing-stray”, with configurable separator and
+//!   suffixes
+//! - `ubbi-dubbi`: “ub” before every vowel sound, “hello” → “hubellubo”
+//! - `opish`: “op” after every consonant, “cat” → “copatop”
+//! - `verlan`: the syllables in reverse order, “bonjour” → “jourbon”
+//!
+//! Like `pig_latin`, every dialect keeps punctuation and whitespace as
+//! they are and gives each word back its capitalisation.
+//!
+
+use std::io::{self, BufRead, Write};
+
+use crate::pig_latin::{self, tokens, vowel_mask, with_case};
+
+pub mod cli;
+
+pub trait Dialect {
+    /// Rewrite one word: letters, plus apostrophes between letters.
+    fn word(&self, word: &str) -> String;
+
+    /// Rewrite every word of `text`, keeping everything between them.
+    fn text(&self, text: &str) -> String {
+        tokens(text)
+            .into_iter()
+            .map(|(is_word, token)| {
+                if is_word {
+                    self.word(token)
+                } else {
+                    token.to_string()
+                }
+            })
+            .collect()
+    }
+}
+
+/// The names `by_name` understands.
+pub const NAMES: &[&str] = &["pig-latin", "ubbi-dubbi", "opish", "verlan"];
+
+/// The dialect called `name`, with its default settings.
+pub fn by_name(name: &str) -> Option<Box<dyn Dialect>> {
+    match name {
+        "pig-latin" => Some(Box::new(PigLatin::new())),
+        "ubbi-dubbi" => Some(Box::new(UbbiDubbi)),
+        "opish" => Some(Box::new(Opish)),
+        "verlan" => Some(Box::new(Verlan)),
+        _ => None,
+    }
+}
+
+/// Rewrite everything read from `input` line by line and write it to
+/// `output`. Words never span lines, so this gives the same result as
+/// `dialect.text` on the whole document.
+pub fn transform<R: BufRead, W: Write>(
+    dialect: &dyn Dialect,
+    mut input: R,
+    mut output: W,
+) -> io::Result<()> {
+    let mut line = String::new();
+    while input.read_line(&mut line)? > 0 {
+        output.write_all(dialect.text(&line).as_bytes())?;
+        line.clear();
+    }
+    output.flush()
+}
+
+/// Pig latin as in `pig_latin::translate_word`, with the separator and the
+/// suffixes configurable. The defaults give “ing-stray” and “apple-hay”;
+/// `PigLatin::new().separator("").vowel_suffix("way")` gives “ingstray”
+/// and “appleway”.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PigLatin {
+    separator: String,
+    vowel_suffix: String,
+    consonant_suffix: String,
+}
+
+impl PigLatin {
+    pub fn new() -> Self {
+        PigLatin {
+            separator: "-".to_string(),
+            vowel_suffix: "hay".to_string(),
+            consonant_suffix: "ay".to_string(),
+        }
+    }
+
+    /// Put between the word and the moved cluster (default `-`).
+    pub fn separator(mut self, separator: &str) -> Self {
+        self.separator = separator.to_string();
+        self
+    }
+
+    /// Added to words starting with a vowel (default `hay`).
+    pub fn vowel_suffix(mut self, suffix: &str) -> Self {
+        self.vowel_suffix = suffix.to_string();
+        self
+    }
+
+    /// Added after the moved consonant cluster (default `ay`).
+    pub fn consonant_suffix(mut self, suffix: &str) -> Self {
+        self.consonant_suffix = suffix.to_string();
+        self
+    }
+}
+
+impl Default for PigLatin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Dialect for PigLatin {
+    fn word(&self, word: &str) -> String {
+        pig_latin::translate_with(
+            word,
+            &self.separator,
+            &self.vowel_suffix,
+            &self.consonant_suffix,
+        )
+    }
+}
+
+/// “ub” before every run of vowels: “ubbi dubbi” → “ububbubi dububbubi”.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UbbiDubbi;
+
+impl Dialect for UbbiDubbi {
+    fn word(&self, word: &str) -> String {
+        with_case(word, |word| {
+            let mut out = String::new();
+            let mut prev_vowel = false;
+            for (c, vowel) in word.chars().zip(vowel_mask(word)) {
+                if vowel && !prev_vowel {
+                    out.push_str("ub");
+                }
+                out.push(c);
+                prev_vowel = vowel;
+            }
+            out
+        })
+    }
+}
+
+/// “op” after every consonant: “cat” → “copatop”.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Opish;
+
+impl Dialect for Opish {
+    fn word(&self, word: &str) -> String {
+        with_case(word, |word| {
+            let mut out = String::new();
+            for (c, vowel) in word.chars().zip(vowel_mask(word)) {
+                out.push(c);
+                if !vowel && c.is_alphabetic() {
+                    out.push_str("op");
+                }
+            }
+            out
+        })
+    }
+}
+
+/// The syllables of each word in reverse order, as in French verlan:
+/// “merci” → “cimer”. A syllable is a run of vowels with the consonants
+/// before it; between two vowel runs only the last consonant (or “qu”)
+/// starts the next syllable. Words of one syllable stay as they are.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Verlan;
+
+/// Split `word` into syllables, see `Verlan`.
+fn syllables(word: &str) -> Vec<String> {
+    let chars: Vec<char> = word.chars().collect();
+    let mask = vowel_mask(word);
+    let mut starts = vec![0];
+    let mut i = 0;
+    while i < chars.len() {
+        if !mask[i] {
+            i += 1;
+            continue;
+        }
+        // skip the vowel run, then the consonants after it
+        while i < chars.len() && mask[i] {
+            i += 1;
+        }
+        let consonants = i;
+        while i < chars.len() && !mask[i] {
+            i += 1;
+        }
+        if i == chars.len() {
+            break;
+        }
+        let onset = if i - consonants >= 2 && chars[i - 2..i] == ['q', 'u'] {
+            2
+        } else {
+            1
+        };
+        starts.push(i - onset);
+    }
+    starts.push(chars.len());
+    starts
+        .windows(2)
+        .map(|pair| chars[pair[0]..pair[1]].iter().collect())
+        .collect()
+}
+
+impl Dialect for Verlan {
+    fn word(&self, word: &str) -> String {
+        with_case(word, |word| syllables(word).into_iter().rev().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn pig_latin_defaults_and_suffixes() {
+        let text = "Hello, apple psst!";
+        assert_eq!(PigLatin::new().text(text), pig_latin::pig_latin(text));
+        let custom = PigLatin::new()
+            .separator("")
+            .vowel_suffix("way")
+            .consonant_suffix("ay");
+        assert_eq!(custom.text(text), "Ellohay, appleway psstay!");
+    }
+
+    #[test]
+    fn ubbi_dubbi() {
+        assert_eq!(UbbiDubbi.text("Ubbi dubbi"), "Ububbubi dububbubi");
+        assert_eq!(UbbiDubbi.text("hello, QUEEN"), "hubellubo, QUUBEEN");
+        assert_eq!(UbbiDubbi.text("my yes"), "muby yubes");
+    }
+
+    #[test]
+    fn opish() {
+        assert_eq!(Opish.text("Cat sat."), "Copatop sopatop.");
+        assert_eq!(Opish.text("I"), "I");
+    }
+
+    #[test]
+    fn verlan() {
+        assert_eq!(
+            Verlan.text("Bonjour merci parents"),
+            "Jourbon cimer rentspa"
+        );
+        assert_eq!(Verlan.text("banquet chat"), "quetban chat");
+        assert_eq!(syllables("femme"), vec!["fem", "me"]);
+    }
+
+    #[test]
+    fn selects_by_name_and_streams() {
+        for name in NAMES {
+            assert!(by_name(name).is_some());
+        }
+        assert!(by_name("klingon").is_none());
+
+        let dialect = by_name("opish").unwrap();
+        let mut output = Vec::new();
+        transform(dialect.as_ref(), Cursor::new("cat\n\nhot dog"), &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "copatop\n\nhopotop dopogop"
+        );
+    }
+}
@@ -0,0 +1,117 @@
+//!
+//! `collections dialect NAME [FILE] [--separator S] [--vowel-suffix S]
+//! [--consonant-suffix S]`
+//!
+//! Rewrites FILE (or stdin when FILE is missing or `-`) in the dialect
+//! called NAME and prints the result as it goes. The suffix options only
+//! apply to `pig-latin`.
+//!
+
+use std::io;
+
+use super::{Dialect, NAMES, PigLatin, by_name, transform};
+use crate::cli;
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Options {
+    pub name: String,
+    pub path: Option<String>,
+    pub separator: Option<String>,
+    pub vowel_suffix: Option<String>,
+    pub consonant_suffix: Option<String>,
+}
+
+pub fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut name = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let slot = match arg.as_str() {
+            "--separator" => &mut options.separator,
+            "--vowel-suffix" => &mut options.vowel_suffix,
+            "--consonant-suffix" => &mut options.consonant_suffix,
+            flag if flag.starts_with("--") => return Err(format!("unknown option {}", flag)),
+            value if name.is_none() => {
+                name = Some(value.to_string());
+                continue;
+            }
+            path if options.path.is_none() => {
+                options.path = Some(path.to_string());
+                continue;
+            }
+            extra => return Err(format!("unexpected argument {}", extra)),
+        };
+        let value = args.next().ok_or(format!("{} needs a value", arg))?;
+        *slot = Some(value.clone());
+    }
+    options.name = name.ok_or(format!("missing dialect, one of {}", NAMES.join(", ")))?;
+    Ok(options)
+}
+
+/// The dialect the options ask for.
+pub fn dialect(options: &Options) -> Result<Box<dyn Dialect>, String> {
+    let customised = options.separator.is_some()
+        || options.vowel_suffix.is_some()
+        || options.consonant_suffix.is_some();
+    if options.name == "pig-latin" {
+        let mut pig_latin = PigLatin::new();
+        if let Some(separator) = &options.separator {
+            pig_latin = pig_latin.separator(separator);
+        }
+        if let Some(suffix) = &options.vowel_suffix {
+            pig_latin = pig_latin.vowel_suffix(suffix);
+        }
+        if let Some(suffix) = &options.consonant_suffix {
+            pig_latin = pig_latin.consonant_suffix(suffix);
+        }
+        return Ok(Box::new(pig_latin));
+    }
+    if customised {
+        return Err(format!("{} has no suffixes to configure", options.name));
+    }
+    by_name(&options.name).ok_or(format!(
+        "unknown dialect {}, expected one of {}",
+        options.name,
+        NAMES.join(", ")
+    ))
+}
+
+/// Run the `dialect` command with the arguments after `dialect`. Returns
+/// the process exit status.
+pub fn run(args: &[String]) -> i32 {
+    cli::exit_status("dialect", try_run(args).map(|()| 0))
+}
+
+fn try_run(args: &[String]) -> Result<(), String> {
+    let options = parse_args(args)?;
+    let dialect = dialect(&options)?;
+    let (name, input) = cli::open_input(options.path.as_deref())?;
+    transform(dialect.as_ref(), input, io::stdout().lock()).map_err(|e| format!("{}: {}", name, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::testing::args;
+
+    #[test]
+    fn parses_options() {
+        let options = parse_args(&args(&["pig-latin", "in.txt", "--separator", ""])).unwrap();
+        assert_eq!(options.name, "pig-latin");
+        assert_eq!(options.path.as_deref(), Some("in.txt"));
+        assert_eq!(options.separator.as_deref(), Some(""));
+        assert!(parse_args(&args(&[])).is_err());
+        assert!(parse_args(&args(&["opish", "--vowel-suffix"])).is_err());
+        assert!(parse_args(&args(&["opish", "a", "b"])).is_err());
+    }
+
+    #[test]
+    fn builds_the_dialect() {
+        let options = parse_args(&args(&["pig-latin", "--vowel-suffix", "way"])).unwrap();
+        assert_eq!(dialect(&options).unwrap().text("apple"), "apple-way");
+        let options = parse_args(&args(&["verlan", "--separator", ""])).unwrap();
+        assert!(dialect(&options).is_err());
+        let options = parse_args(&args(&["klingon"])).unwrap();
+        assert!(dialect(&options).is_err());
+    }
+}
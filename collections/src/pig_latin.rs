@@ -0,0 +1,465 @@
+//!
+//! Convert strings to pig latin. The consonants before the first vowel
+//! of each word are moved to the end of the word and “ay” is added, so
+//! “first” becomes “irst-fay” and “string” becomes “ing-stray”. Words
+//! that start with a vowel have “hay” added to the end instead (“apple”
+//! becomes “apple-hay”).
+//!
+//! Everything that is not part of a word (whitespace, punctuation,
+//! digits) is copied through unchanged, and each word keeps its
+//! capitalisation: “Hello, World!” becomes “Ello-hay, Orld-way!”.
+//!
+//! `decode` turns that form back into the original text. The encoding
+//! loses information in a few places — “ello-hay” may have been “ello”
+//! or “hello” — so every word that has more than one possible original
+//! (or that is not pig latin at all) is reported next to the result.
+//!
+
+use std::fmt;
+
+/// Vowels, in lowercase. Besides the plain ASCII ones this covers the
+/// accented Latin vowels and the Cyrillic ones, so words in other
+/// languages are split in a sensible place.
+const VOWELS: &[char] = &[
+    'a', 'e', 'i', 'o', 'u', 'à', 'á', 'â', 'ã', 'ä', 'å', 'æ', 'è', 'é', 'ê', 'ë', 'ì', 'í', 'î',
+    'ï', 'ò', 'ó', 'ô', 'õ', 'ö', 'ø', 'ù', 'ú', 'û', 'ü', 'ā', 'ē', 'ī', 'ō', 'ū', 'œ', 'а', 'е',
+    'ё', 'и', 'о', 'у', 'ы', 'э', 'ю', 'я',
+];
+
+fn lower(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+fn is_vowel(c: char) -> bool {
+    VOWELS.contains(&lower(c))
+}
+
+/// Apostrophes only belong to a word between two letters, as in “don't”.
+fn is_apostrophe(c: char) -> bool {
+    c == '\'' || c == '’'
+}
+
+/// Which chars of `word` count as vowels.
+///
+/// - `y` is a consonant at the start of a word (“yellow”) and a vowel
+///   anywhere else (“rhythm”, “my”)
+/// - a `u` after `q` is a consonant (“queen” → “een-quay”)
+pub(crate) fn vowel_mask(word: &str) -> Vec<bool> {
+    let mut prev = None;
+    let mut mask = Vec::new();
+    for (i, c) in word.chars().enumerate() {
+        let c = lower(c);
+        mask.push(if c == 'u' && prev == Some('q') {
+            false
+        } else {
+            is_vowel(c) || (c == 'y' && i > 0)
+        });
+        prev = Some(c);
+    }
+    mask
+}
+
+/// Byte index where the leading consonant cluster of `word` ends. A word
+/// without any vowel is all cluster.
+pub(crate) fn cluster_end(word: &str) -> usize {
+    word.char_indices()
+        .zip(vowel_mask(word))
+        .find(|(_, vowel)| *vowel)
+        .map_or(word.len(), |((i, _), _)| i)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Case {
+    /// “hello”, or anything not covered below (“iPhone”): kept as is.
+    AsIs,
+    /// “Hello”
+    Title,
+    /// “HELLO”
+    Upper,
+}
+
+fn case_of(word: &str) -> Case {
+    let mut letters = word.chars().filter(|c| c.is_alphabetic());
+    let Some(first) = letters.next() else {
+        return Case::AsIs;
+    };
+    let rest: Vec<char> = letters.collect();
+    if !first.is_uppercase() {
+        Case::AsIs
+    } else if !rest.is_empty() && rest.iter().all(|c| c.is_uppercase()) {
+        Case::Upper
+    } else if rest.iter().all(|c| !c.is_uppercase()) {
+        Case::Title
+    } else {
+        Case::AsIs
+    }
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Apply `f` to the lowercase form of a capitalised or all-caps word and
+/// give the result the same case; other words are passed through as is.
+pub(crate) fn with_case(word: &str, f: impl FnOnce(&str) -> String) -> String {
+    match case_of(word) {
+        Case::AsIs => f(word),
+        Case::Title => capitalize(&f(&word.to_lowercase())),
+        Case::Upper => f(&word.to_lowercase()).to_uppercase(),
+    }
+}
+
+/// Translate a single word, without any surrounding punctuation.
+/// # Examples
+/// ```
+/// assert_eq!(translate_word("String"), "Ing-stray");
+/// ```
+pub fn translate_word(word: &str) -> String {
+    translate_with(word, "-", "hay", "ay")
+}
+
+/// `translate_word` with other separator and suffixes: `vowel_suffix`
+/// follows words starting with a vowel, `consonant_suffix` the moved
+/// cluster.
+pub(crate) fn translate_with(
+    word: &str,
+    separator: &str,
+    vowel_suffix: &str,
+    consonant_suffix: &str,
+) -> String {
+    with_case(word, |word| {
+        let (cluster, rest) = word.split_at(cluster_end(word));
+        if cluster.is_empty() {
+            format!("{}{}{}", rest, separator, vowel_suffix)
+        } else if rest.is_empty() {
+            format!("{}{}{}", cluster, separator, consonant_suffix)
+        } else {
+            format!("{}{}{}{}", rest, separator, cluster, consonant_suffix)
+        }
+    })
+}
+
+/// Split `text` into words and the text between them. Each item is
+/// `(is_word, slice)`; concatenating the slices gives back `text`.
+pub(crate) fn tokens(text: &str) -> Vec<(bool, &str)> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut in_word = false;
+    for (n, &(i, c)) in chars.iter().enumerate() {
+        let word_char = c.is_alphabetic()
+            || (in_word
+                && is_apostrophe(c)
+                && chars
+                    .get(n + 1)
+                    .is_some_and(|(_, next)| next.is_alphabetic()));
+        if word_char != in_word && i > start {
+            tokens.push((in_word, &text[start..i]));
+            start = i;
+        }
+        in_word = word_char;
+    }
+    if start < text.len() {
+        tokens.push((in_word, &text[start..]));
+    }
+    tokens
+}
+
+/// Translate every word of `text`, keeping everything around the words
+/// exactly as it was.
+/// # Examples
+/// ```
+/// assert_eq!(pig_latin("Hello,  world!"), "Ello-hay,  orld-way!");
+/// ```
+pub fn pig_latin(text: &str) -> String {
+    tokens(text)
+        .into_iter()
+        .map(|(is_word, token)| {
+            if is_word {
+                translate_word(token)
+            } else {
+                token.to_string()
+            }
+        })
+        .collect()
+}
+
+/// Why a word in the input of `decode` could not be turned back into
+/// exactly one original word.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Problem {
+    /// Several words encode to `word`; the first candidate was used.
+    Ambiguous {
+        word: String,
+        candidates: Vec<String>,
+    },
+    /// `word` is not something `pig_latin` produces; it was kept as is.
+    NotPigLatin { word: String },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::Ambiguous { word, candidates } => {
+                write!(f, "`{}` is ambiguous: {}", word, candidates.join(" or "))
+            }
+            Problem::NotPigLatin { word } => write!(f, "`{}` is not pig latin", word),
+        }
+    }
+}
+
+/// The result of `decode`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decoded {
+    pub text: String,
+    pub problems: Vec<Problem>,
+}
+
+/// Every word that `translate_word` turns into `left-right`, sorted.
+///
+/// The suffix says where the word was split: `-hay` for a word starting
+/// with a vowel, `-ay` for a word without one and `-Xay` for the cluster
+/// `X`. A cluster of `h` is indistinguishable from `-hay`, so all readings
+/// are tried and only the ones that encode back to the input are kept.
+///
+/// Words that differ only in case are not told apart: “strIng” encodes to
+/// “Ing-stray” just like “String” does, and the spelling whose case
+/// matches the input (lowercase, capitalised or all caps) wins.
+pub fn decode_word(left: &str, right: &str) -> Vec<String> {
+    let encoded = format!("{}-{}", left, right);
+    let mut candidates = Vec::new();
+    for (left, right) in [
+        (left.to_string(), right.to_string()),
+        (left.to_lowercase(), right.to_lowercase()),
+    ] {
+        let Some(cluster) = right
+            .strip_suffix("ay")
+            .or_else(|| right.strip_suffix("AY"))
+        else {
+            continue;
+        };
+        let mut readings = vec![format!("{}{}", cluster, left)];
+        if cluster.is_empty() || cluster.eq_ignore_ascii_case("h") {
+            readings.push(left.clone());
+        }
+        for word in readings {
+            candidates.push(capitalize(&word));
+            candidates.push(word.to_uppercase());
+            candidates.push(word);
+        }
+    }
+    candidates.retain(|word| translate_word(word) == encoded);
+    let case = case_of(&encoded);
+    candidates.sort_by_key(|word| (word.to_lowercase(), case_of(word) != case));
+    candidates.dedup_by_key(|word| word.to_lowercase());
+    candidates
+}
+
+/// Turn text produced by `pig_latin` back into the original, keeping
+/// everything between the words as it is.
+/// # Examples
+/// ```
+/// let decoded = decode("Ing-stray, apple-hay!");
+/// assert_eq!(decoded.text, "String, apple!");
+/// assert!(decoded.problems.is_empty());
+/// ```
+pub fn decode(text: &str) -> Decoded {
+    let tokens = tokens(text);
+    let mut decoded = Decoded {
+        text: String::new(),
+        problems: Vec::new(),
+    };
+    let mut i = 0;
+    while i < tokens.len() {
+        let (is_word, token) = tokens[i];
+        if !is_word {
+            decoded.text.push_str(token);
+            i += 1;
+            continue;
+        }
+        let candidates = match (tokens.get(i + 1), tokens.get(i + 2)) {
+            (Some((false, "-")), Some((true, right))) => decode_word(token, right),
+            _ => Vec::new(),
+        };
+        let Some(first) = candidates.first() else {
+            decoded.text.push_str(token);
+            decoded.problems.push(Problem::NotPigLatin {
+                word: token.to_string(),
+            });
+            i += 1;
+            continue;
+        };
+        decoded.text.push_str(first);
+        if candidates.len() > 1 {
+            decoded.problems.push(Problem::Ambiguous {
+                word: tokens[i..i + 3].iter().map(|(_, t)| *t).collect(),
+                candidates,
+            });
+        }
+        i += 3;
+    }
+    decoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn vowels_and_consonants() {
+        assert_eq!(pig_latin("apple"), "apple-hay");
+        assert_eq!(pig_latin("first"), "irst-fay");
+        assert_eq!(pig_latin(""), "");
+    }
+
+    #[test]
+    fn consonant_clusters() {
+        assert_eq!(pig_latin("string"), "ing-stray");
+        assert_eq!(pig_latin("three"), "ee-thray");
+        assert_eq!(pig_latin("queen squeal"), "een-quay eal-squay");
+    }
+
+    #[test]
+    fn y_is_a_consonant_only_at_the_start() {
+        assert_eq!(pig_latin("yellow"), "ellow-yay");
+        assert_eq!(pig_latin("rhythm"), "ythm-rhay");
+        assert_eq!(pig_latin("my"), "y-may");
+        assert_eq!(pig_latin("psst"), "psst-ay");
+    }
+
+    #[test]
+    fn capitalisation_is_preserved() {
+        assert_eq!(pig_latin("Apple"), "Apple-hay");
+        assert_eq!(pig_latin("String"), "Ing-stray");
+        assert_eq!(pig_latin("NASA"), "ASA-NAY");
+        assert_eq!(pig_latin("I"), "I-hay");
+        assert_eq!(pig_latin("iPhone"), "iPhone-hay");
+    }
+
+    #[test]
+    fn punctuation_and_whitespace_are_preserved() {
+        assert_eq!(pig_latin("hello,"), "ello-hay,");
+        assert_eq!(
+            pig_latin("  apple\tfirst  under.\n"),
+            "  apple-hay\tirst-fay  under-hay.\n"
+        );
+        assert_eq!(pig_latin("don't stop"), "on't-day op-stay");
+        assert_eq!(pig_latin("'quoted' 42"), "'oted-quay' 42");
+    }
+
+    #[test]
+    fn non_ascii_letters() {
+        assert_eq!(pig_latin("Ärger"), "Ärger-hay");
+        assert_eq!(pig_latin("schön"), "ön-schay");
+        assert_eq!(pig_latin("Привет"), "Ивет-прay");
+    }
+
+    #[test]
+    fn tokens_cover_the_whole_text() {
+        let text = " a,b  'c' ";
+        let joined: String = tokens(text).iter().map(|(_, t)| *t).collect();
+        assert_eq!(joined, text);
+    }
+
+    #[test]
+    fn decodes_what_it_encodes() {
+        let decoded = decode("Ing-stray, een-quay ASA-NAY! psst-ay on't-day ythm-rhay.");
+        assert_eq!(decoded.text, "String, queen NASA! psst don't rhythm.");
+        assert!(decoded.problems.is_empty());
+        assert_eq!(decode("Ивет-прay").text, "Привет");
+    }
+
+    #[test]
+    fn reports_ambiguous_words() {
+        assert_eq!(decode_word("ello", "hay"), vec!["ello", "hello"]);
+        let decoded = decode("I-hay ove-lay");
+        assert_eq!(decoded.text, "Hi love");
+        assert_eq!(
+            decoded.problems,
+            vec![Problem::Ambiguous {
+                word: "I-hay".to_string(),
+                candidates: vec!["Hi".to_string(), "I".to_string()],
+            }]
+        );
+    }
+
+    #[test]
+    fn reports_words_that_are_not_pig_latin() {
+        let decoded = decode("hello orld-way ing-stra");
+        assert_eq!(decoded.text, "hello world ing-stra");
+        assert_eq!(
+            decoded.problems,
+            vec![
+                Problem::NotPigLatin {
+                    word: "hello".to_string()
+                },
+                Problem::NotPigLatin {
+                    word: "ing".to_string()
+                },
+                Problem::NotPigLatin {
+                    word: "stra".to_string()
+                },
+            ]
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn round_trips(text in "[a-zA-ZäöüéÄÖÉпрвтиоПРИ' ,.!?0-9\n-]{0,40}") {
+            let decoded = decode(&pig_latin(&text));
+            let mut uniform = true;
+            for (is_word, word) in tokens(&text) {
+                if !is_word {
+                    continue;
+                }
+                let encoded = translate_word(word);
+                let (left, right) = encoded.split_once('-').unwrap();
+                let candidates = decode_word(left, right);
+                // only the case of mixed-case words like “iPhone” can be lost
+                let lower = word.to_lowercase();
+                let natural = [lower.clone(), capitalize(&lower), word.to_uppercase()];
+                uniform &= natural.contains(&word.to_string());
+                let found = candidates.iter().any(|c| {
+                    c == word || (!natural.contains(&word.to_string()) && c.to_lowercase() == lower)
+                });
+                prop_assert!(found, "{} decoded to {:?}", word, candidates);
+            }
+            let not_pig_latin = decoded
+                .problems
+                .iter()
+                .any(|p| matches!(p, Problem::NotPigLatin { .. }));
+            prop_assert!(!not_pig_latin);
+            if decoded.problems.is_empty() {
+                prop_assert_eq!(decoded.text.to_lowercase(), text.to_lowercase());
+                // without mixed-case words nothing is lost at all
+                if uniform {
+                    prop_assert_eq!(&decoded.text, &text);
+                }
+            }
+        }
+
+        #[test]
+        fn restores_uniform_case_exactly(
+            words in prop::collection::vec(("[a-zäöüпрвтио]{1,8}", 0..3usize), 0..8)
+        ) {
+            let words: Vec<String> = words
+                .into_iter()
+                .map(|(word, case)| match case {
+                    0 => word,
+                    1 => capitalize(&word),
+                    _ => word.to_uppercase(),
+                })
+                .collect();
+            let text = words.join(" ");
+            let decoded = decode(&pig_latin(&text));
+            if decoded.problems.is_empty() {
+                prop_assert_eq!(decoded.text, text);
+            }
+        }
+    }
+}
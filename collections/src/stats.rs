@@ -0,0 +1,264 @@
+//!
+//! Descriptive statistics over numeric slices.
+//!
+//! Every function works on any primitive number type and leaves the input
+//! untouched; the ones that need the data in order sort a copy. Functions
+//! return `None` (or an empty `Vec`) for an empty slice instead of
+//! panicking.
+//!
+
+use std::cmp::Ordering;
+use std::fmt;
+
+pub mod cli;
+pub mod running;
+
+/// A primitive number the statistics can be computed over.
+pub trait Number: Copy + PartialOrd + fmt::Display {
+    fn to_f64(self) -> f64;
+}
+
+macro_rules! impl_number {
+    ($($t:ty),*) => {
+        $(
+            impl Number for $t {
+                fn to_f64(self) -> f64 {
+                    self as f64
+                }
+            }
+        )*
+    };
+}
+
+impl_number!(
+    i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64
+);
+
+/// Order two numbers, placing NaN after every other value so sorting a
+/// float slice never panics.
+fn compare<T: Number>(a: &T, b: &T) -> Ordering {
+    a.partial_cmp(b)
+        .unwrap_or_else(|| a.to_f64().total_cmp(&b.to_f64()))
+}
+
+fn sorted<T: Number>(data: &[T]) -> Vec<T> {
+    let mut sorted = data.to_vec();
+    sorted.sort_by(compare);
+    sorted
+}
+
+pub fn mean<T: Number>(data: &[T]) -> Option<f64> {
+    if data.is_empty() {
+        return None;
+    }
+    Some(data.iter().map(|x| x.to_f64()).sum::<f64>() / data.len() as f64)
+}
+
+/// The middle value once sorted; the mean of the two middle values when
+/// the length is even.
+/// # Examples
+/// ```
+/// assert_eq!(median(&[5, 1, 4, 2]), Some(3.0));
+/// ```
+pub fn median<T: Number>(data: &[T]) -> Option<f64> {
+    percentile(data, 50.0)
+}
+
+/// Every value that occurs most often, in ascending order. When several
+/// values tie for the highest count they are all modes.
+/// # Examples
+/// ```
+/// assert_eq!(modes(&[3, 1, 3, 1, 2]), vec![1, 3]);
+/// ```
+pub fn modes<T: Number>(data: &[T]) -> Vec<T> {
+    let sorted = sorted(data);
+    let mut runs: Vec<(T, usize)> = Vec::new();
+    for x in sorted {
+        match runs.last_mut() {
+            Some((value, count)) if *value == x => *count += 1,
+            _ => runs.push((x, 1)),
+        }
+    }
+    let max = runs.iter().map(|(_, count)| *count).max().unwrap_or(0);
+    runs.into_iter()
+        .filter(|(_, count)| *count == max)
+        .map(|(value, _)| value)
+        .collect()
+}
+
+/// The smallest and the largest value.
+pub fn min_max<T: Number>(data: &[T]) -> Option<(T, T)> {
+    let mut min = *data.first()?;
+    let mut max = min;
+    for &x in data {
+        if compare(&x, &min) == Ordering::Less {
+            min = x;
+        }
+        if compare(&x, &max) == Ordering::Greater {
+            max = x;
+        }
+    }
+    Some((min, max))
+}
+
+/// The difference between the largest and the smallest value, computed in
+/// `f64` so it cannot overflow the element type.
+pub fn range<T: Number>(data: &[T]) -> Option<f64> {
+    min_max(data).map(|(min, max)| max.to_f64() - min.to_f64())
+}
+
+/// Population variance: the mean squared distance from the mean.
+pub fn variance<T: Number>(data: &[T]) -> Option<f64> {
+    let mean = mean(data)?;
+    let squares: f64 = data.iter().map(|x| (x.to_f64() - mean).powi(2)).sum();
+    Some(squares / data.len() as f64)
+}
+
+/// Population standard deviation.
+pub fn std_dev<T: Number>(data: &[T]) -> Option<f64> {
+    variance(data).map(f64::sqrt)
+}
+
+/// The `p`-th percentile (0 to 100), interpolating linearly between the
+/// two closest ranks. `None` when `data` is empty or `p` is out of range.
+/// # Examples
+/// ```
+/// assert_eq!(percentile(&[1, 2, 3, 4], 25.0), Some(1.75));
+/// ```
+pub fn percentile<T: Number>(data: &[T], p: f64) -> Option<f64> {
+    if data.is_empty() || !(0.0..=100.0).contains(&p) {
+        return None;
+    }
+    let sorted = sorted(data);
+    let rank = p / 100.0 * (sorted.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    let (a, b) = (sorted[lower].to_f64(), sorted[upper].to_f64());
+    Some(a + (b - a) * (rank - lower as f64))
+}
+
+/// Everything above at once, for printing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary<T> {
+    pub count: usize,
+    pub mean: f64,
+    pub median: f64,
+    pub modes: Vec<T>,
+    pub min: T,
+    pub max: T,
+    pub range: f64,
+    pub variance: f64,
+    pub std_dev: f64,
+    pub p25: f64,
+    pub p75: f64,
+    pub p90: f64,
+}
+
+impl<T: Number> Summary<T> {
+    pub fn new(data: &[T]) -> Option<Self> {
+        let (min, max) = min_max(data)?;
+        Some(Summary {
+            count: data.len(),
+            mean: mean(data)?,
+            median: median(data)?,
+            modes: modes(data),
+            min,
+            max,
+            range: range(data)?,
+            variance: variance(data)?,
+            std_dev: std_dev(data)?,
+            p25: percentile(data, 25.0)?,
+            p75: percentile(data, 75.0)?,
+            p90: percentile(data, 90.0)?,
+        })
+    }
+}
+
+impl<T: Number> fmt::Display for Summary<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let modes: Vec<String> = self.modes.iter().map(|m| m.to_string()).collect();
+        writeln!(f, "count    : {}", self.count)?;
+        writeln!(f, "mean     : {}", self.mean)?;
+        writeln!(f, "median   : {}", self.median)?;
+        writeln!(f, "mode     : {}", modes.join(", "))?;
+        writeln!(f, "min      : {}", self.min)?;
+        writeln!(f, "max      : {}", self.max)?;
+        writeln!(f, "range    : {}", self.range)?;
+        writeln!(f, "variance : {}", self.variance)?;
+        writeln!(f, "std dev  : {}", self.std_dev)?;
+        writeln!(f, "p25      : {}", self.p25)?;
+        writeln!(f, "p75      : {}", self.p75)?;
+        write!(f, "p90      : {}", self.p90)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_input() {
+        let empty: [i32; 0] = [];
+        assert_eq!(mean(&empty), None);
+        assert_eq!(median(&empty), None);
+        assert!(modes(&empty).is_empty());
+        assert_eq!(range(&empty), None);
+        assert_eq!(variance(&empty), None);
+        assert_eq!(std_dev(&empty), None);
+        assert_eq!(percentile(&empty, 50.0), None);
+        assert_eq!(Summary::new(&empty), None);
+    }
+
+    #[test]
+    fn single_element() {
+        let summary = Summary::new(&[7]).unwrap();
+        assert_eq!(summary.mean, 7.0);
+        assert_eq!(summary.median, 7.0);
+        assert_eq!(summary.modes, vec![7]);
+        assert_eq!((summary.min, summary.max), (7, 7));
+        assert_eq!(summary.range, 0.0);
+        assert_eq!(summary.variance, 0.0);
+        assert_eq!(summary.p90, 7.0);
+    }
+
+    #[test]
+    fn median_sorts_first() {
+        assert_eq!(median(&[5, 1, 3]), Some(3.0));
+        // even length: the mean of the two middle values, not `a + b / 2`
+        assert_eq!(median(&[4, 1, 3, 2]), Some(2.5));
+        assert_eq!(median(&[1, 2, 3, 4, 5, 5, 5]), Some(4.0));
+    }
+
+    #[test]
+    fn all_modes_on_ties() {
+        assert_eq!(modes(&[1, 2, 3, 4, 5, 5, 5]), vec![5]);
+        assert_eq!(modes(&[2, 1, 2, 1, 3]), vec![1, 2]);
+        assert_eq!(modes(&[0.5, 0.25]), vec![0.25, 0.5]);
+    }
+
+    #[test]
+    fn spread() {
+        let data = [2, 4, 4, 4, 5, 5, 7, 9];
+        assert_eq!(mean(&data), Some(5.0));
+        assert_eq!(variance(&data), Some(4.0));
+        assert_eq!(std_dev(&data), Some(2.0));
+        assert_eq!(range(&data), Some(7.0));
+        assert_eq!(range(&[i32::MIN, i32::MAX]), Some(u32::MAX as f64));
+    }
+
+    #[test]
+    fn percentiles_interpolate() {
+        let data = [15, 20, 35, 40, 50];
+        assert_eq!(percentile(&data, 0.0), Some(15.0));
+        assert_eq!(percentile(&data, 40.0), Some(29.0));
+        assert_eq!(percentile(&data, 100.0), Some(50.0));
+        assert_eq!(percentile(&data, 101.0), None);
+    }
+
+    #[test]
+    fn floats_with_nan_do_not_panic() {
+        let data = [3.0, f64::NAN, 1.0];
+        assert_eq!(min_max(&data).map(|(min, _)| min), Some(1.0));
+        assert_eq!(percentile(&data, 0.0), Some(1.0));
+    }
+}
@@ -0,0 +1,349 @@
+//!
+//! `collections stats [FILE] [--csv COLUMN] [--strict] [--json] [--running]`
+//!
+//! Reads numbers from FILE (or stdin when FILE is missing or `-`) and
+//! prints the full statistics summary. Plain input may hold any number of
+//! whitespace-separated values per line; with `--csv` the named column (a
+//! header name or a 1-based index) of a comma-separated file is used.
+//! Values that are not numbers are skipped and reported with their line
+//! number on stderr, or abort the run with `--strict`.
+//!
+//! With `--running` plain input is read one line at a time without being
+//! kept, the count, mean and median so far are printed after every line
+//! with numbers on it, and the summary at the end.
+//!
+
+use std::io::{BufRead, Read};
+
+use serde_json::{Value, json};
+
+use super::Summary;
+use super::running::RunningStats;
+use crate::cli;
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Options {
+    pub path: Option<String>,
+    pub column: Option<String>,
+    pub strict: bool,
+    pub json: bool,
+    pub running: bool,
+}
+
+/// A value that could not be read as a number.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Malformed {
+    pub line: usize,
+    pub value: String,
+}
+
+pub fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--csv" => {
+                let column = args.next().ok_or("--csv needs a column name or number")?;
+                options.column = Some(column.clone());
+            }
+            "--strict" => options.strict = true,
+            "--json" => options.json = true,
+            "--running" => options.running = true,
+            flag if flag.starts_with("--") => return Err(format!("unknown option {}", flag)),
+            path if options.path.is_none() => options.path = Some(path.to_string()),
+            extra => return Err(format!("unexpected argument {}", extra)),
+        }
+    }
+    if options.running && (options.column.is_some() || options.json) {
+        return Err("--running only reads plain text".to_string());
+    }
+    Ok(options)
+}
+
+fn parse_number(value: &str) -> Option<f64> {
+    value.parse::<f64>().ok().filter(|x| x.is_finite())
+}
+
+/// Every whitespace-separated value of `text`, split into the numbers and
+/// the values that are not.
+pub fn read_numbers(text: &str) -> (Vec<f64>, Vec<Malformed>) {
+    let mut numbers = Vec::new();
+    let mut malformed = Vec::new();
+    for (line, content) in (1..).zip(text.lines()) {
+        for value in content.split_whitespace() {
+            match parse_number(value) {
+                Some(x) => numbers.push(x),
+                None => malformed.push(Malformed {
+                    line,
+                    value: value.to_string(),
+                }),
+            }
+        }
+    }
+    (numbers, malformed)
+}
+
+/// Split one CSV record. Fields may be quoted, with `""` standing for a
+/// quote inside a quoted field.
+fn split_csv(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+/// The numbers in `column` of a CSV document whose first line is a header.
+/// `column` is a header name or a 1-based column number. Empty cells are
+/// treated as missing and skipped silently.
+pub fn read_csv_column(text: &str, column: &str) -> Result<(Vec<f64>, Vec<Malformed>), String> {
+    let mut lines = (1..).zip(text.lines());
+    let (_, header) = lines.next().ok_or("the CSV input is empty")?;
+    let header = split_csv(header);
+    let index = match header.iter().position(|name| name.trim() == column) {
+        Some(index) => index,
+        None => match column.parse::<usize>() {
+            Ok(n) if (1..=header.len()).contains(&n) => n - 1,
+            _ => return Err(format!("no column `{}` in the CSV header", column)),
+        },
+    };
+    let mut numbers = Vec::new();
+    let mut malformed = Vec::new();
+    for (line, record) in lines {
+        if record.trim().is_empty() {
+            continue;
+        }
+        let fields = split_csv(record);
+        let value = fields.get(index).map_or("", |field| field.trim());
+        if value.is_empty() {
+            continue;
+        }
+        match parse_number(value) {
+            Some(x) => numbers.push(x),
+            None => malformed.push(Malformed {
+                line,
+                value: value.to_string(),
+            }),
+        }
+    }
+    Ok((numbers, malformed))
+}
+
+/// The summary and the skipped values as a single JSON object. JSON has
+/// no infinity or NaN, so statistics that overflowed are `null`.
+pub fn to_json(summary: &Summary<f64>, malformed: &[Malformed]) -> String {
+    let skipped: Vec<Value> = malformed
+        .iter()
+        .map(|m| json!({ "line": m.line, "value": m.value }))
+        .collect();
+    json!({
+        "count": summary.count,
+        "mean": summary.mean,
+        "median": summary.median,
+        "modes": summary.modes,
+        "min": summary.min,
+        "max": summary.max,
+        "range": summary.range,
+        "variance": summary.variance,
+        "std_dev": summary.std_dev,
+        "p25": summary.p25,
+        "p75": summary.p75,
+        "p90": summary.p90,
+        "skipped": skipped,
+    })
+    .to_string()
+}
+
+/// Run the `stats` command with the arguments after `stats`. Returns the
+/// process exit status.
+pub fn run(args: &[String]) -> i32 {
+    cli::exit_status("stats", try_run(args).map(|()| 0))
+}
+
+/// Feed every line of `input` to a `RunningStats` as it is read and write
+/// the statistics so far after each one with numbers on it.
+pub fn run_streaming(
+    input: impl BufRead,
+    strict: bool,
+    mut report: impl FnMut(&str),
+) -> Result<RunningStats<f64>, String> {
+    let mut stats = RunningStats::new();
+    for (line, content) in (1..).zip(input.lines()) {
+        let content = content.map_err(|e| e.to_string())?;
+        let (numbers, malformed) = read_numbers(&content);
+        if let Some(m) = malformed.first() {
+            if strict {
+                return Err(format!("line {}: `{}` is not a number", line, m.value));
+            }
+            for m in &malformed {
+                eprintln!("line {}: skipping `{}`, not a number", line, m.value);
+            }
+        }
+        if numbers.is_empty() {
+            continue;
+        }
+        stats.extend(numbers);
+        report(&format!(
+            "line {}: count {}, mean {}, median {}",
+            line,
+            stats.count(),
+            stats.mean().unwrap_or_default(),
+            stats.median().unwrap_or_default()
+        ));
+    }
+    Ok(stats)
+}
+
+fn try_run(args: &[String]) -> Result<(), String> {
+    let options = parse_args(args)?;
+    let (name, mut input) = cli::open_input(options.path.as_deref())?;
+    if options.running {
+        let report = |line: &str| println!("{}", line);
+        let stats =
+            run_streaming(input, options.strict, report).map_err(|e| format!("{}: {}", name, e))?;
+        if stats.count() == 0 {
+            return Err("no numbers in the input".to_string());
+        }
+        println!("{}", stats);
+        return Ok(());
+    }
+    let mut text = String::new();
+    input
+        .read_to_string(&mut text)
+        .map_err(|e| format!("{}: {}", name, e))?;
+    let (numbers, malformed) = match &options.column {
+        Some(column) => read_csv_column(&text, column)?,
+        None => read_numbers(&text),
+    };
+    if options.strict {
+        if let Some(first) = malformed.first() {
+            return Err(format!(
+                "line {}: `{}` is not a number",
+                first.line, first.value
+            ));
+        }
+    } else {
+        for m in &malformed {
+            eprintln!("line {}: skipping `{}`, not a number", m.line, m.value);
+        }
+    }
+    let summary = Summary::new(&numbers).ok_or("no numbers in the input")?;
+    if options.json {
+        println!("{}", to_json(&summary, &malformed));
+    } else {
+        println!("{}", summary);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::testing::args;
+
+    #[test]
+    fn parses_options() {
+        let options = parse_args(&args(&["data.csv", "--csv", "price", "--json"])).unwrap();
+        assert_eq!(options.path.as_deref(), Some("data.csv"));
+        assert_eq!(options.column.as_deref(), Some("price"));
+        assert!(options.json && !options.strict);
+        assert!(parse_args(&args(&["--csv"])).is_err());
+        assert!(parse_args(&args(&["a", "b"])).is_err());
+    }
+
+    #[test]
+    fn streams_running_statistics() {
+        let mut lines = Vec::new();
+        let stats = run_streaming("5 1\n\nx 4\n2\n".as_bytes(), false, |line| {
+            lines.push(line.to_string())
+        })
+        .unwrap();
+        assert_eq!(
+            lines,
+            vec![
+                "line 1: count 2, mean 3, median 3",
+                "line 3: count 3, mean 3.3333333333333335, median 4",
+                "line 4: count 4, mean 3, median 3",
+            ]
+        );
+        assert_eq!(stats.modes(), vec![1.0, 2.0, 4.0, 5.0]);
+        assert!(run_streaming("1\nx\n".as_bytes(), true, |_| ()).is_err());
+        assert!(parse_args(&args(&["--running", "--json"])).is_err());
+    }
+
+    #[test]
+    fn plain_text_reports_malformed_values_by_line() {
+        let (numbers, malformed) = read_numbers("1 2.5\n  \nthree 4\n-1e2 NaN\n");
+        assert_eq!(numbers, vec![1.0, 2.5, 4.0, -100.0]);
+        assert_eq!(
+            malformed,
+            vec![
+                Malformed {
+                    line: 3,
+                    value: "three".to_string()
+                },
+                Malformed {
+                    line: 4,
+                    value: "NaN".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn csv_column_by_name_or_number() {
+        let csv =
+            "name,\"price, EUR\",qty\napple,\"1.5\",3\npear,,2\n\"plum, red\",n/a,1\nfig,2.5,4\n";
+        let (numbers, malformed) = read_csv_column(csv, "price, EUR").unwrap();
+        assert_eq!(numbers, vec![1.5, 2.5]);
+        assert_eq!(
+            malformed,
+            vec![Malformed {
+                line: 4,
+                value: "n/a".to_string()
+            }]
+        );
+        let (numbers, _) = read_csv_column(csv, "3").unwrap();
+        assert_eq!(numbers, vec![3.0, 2.0, 1.0, 4.0]);
+        assert!(read_csv_column(csv, "weight").is_err());
+        assert!(read_csv_column("", "1").is_err());
+    }
+
+    #[test]
+    fn json_output() {
+        let summary = Summary::new(&[1.0, 2.0, 2.0, 3.0]).unwrap();
+        let json = to_json(
+            &summary,
+            &[Malformed {
+                line: 2,
+                value: "\"x\"".to_string(),
+            }],
+        );
+        let value: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["count"], 4);
+        assert_eq!(value["mean"], 2.0);
+        assert_eq!(value["modes"], json!([2.0]));
+        assert_eq!(value["skipped"], json!([{ "line": 2, "value": "\"x\"" }]));
+    }
+
+    #[test]
+    fn json_output_writes_overflow_as_null() {
+        let summary = Summary::new(&[1e308, 1e308, -1e308]).unwrap();
+        assert!(!summary.variance.is_finite());
+        let value: Value = serde_json::from_str(&to_json(&summary, &[])).unwrap();
+        assert_eq!(value["variance"], Value::Null);
+        assert_eq!(value["max"], 1e308);
+    }
+}
@@ -0,0 +1,244 @@
+//!
+//! Statistics over a stream of numbers that never has to be held in a
+//! `Vec`: values are pushed one at a time and every statistic can be
+//! queried at any point.
+//!
+//! - mean and variance use Welford's online algorithm, O(1) per value
+//! - the median is kept between two heaps, O(log n) per value
+//! - modes come from a frequency counter
+//!
+//! An exact running median has to remember every value, so the heaps grow
+//! with the stream; the counter grows with the number of distinct values.
+//!
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, BinaryHeap};
+use std::fmt;
+
+use super::{Number, compare};
+
+/// Gives any `Number` a total order so it can live in a heap or as a map
+/// key. NaN sorts after every other value.
+#[derive(Debug, Clone, Copy)]
+struct Ordered<T>(T);
+
+impl<T: Number> PartialEq for Ordered<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T: Number> Eq for Ordered<T> {}
+
+impl<T: Number> PartialOrd for Ordered<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Number> Ord for Ordered<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        compare(&self.0, &other.0)
+    }
+}
+
+/// An incremental accumulator for an unbounded stream of numbers.
+/// # Examples
+/// ```
+/// let mut stats = RunningStats::new();
+/// for x in [5, 1, 4] {
+///     stats.push(x);
+/// }
+/// assert_eq!(stats.median(), Some(4.0));
+/// stats.push(2);
+/// assert_eq!(stats.median(), Some(3.0));
+/// ```
+#[derive(Debug, Clone)]
+pub struct RunningStats<T: Number> {
+    count: usize,
+    mean: f64,
+    /// Sum of squared distances from the mean (Welford's M2).
+    m2: f64,
+    /// The smaller half of the values, largest on top.
+    lower: BinaryHeap<Ordered<T>>,
+    /// The larger half of the values, smallest on top. Never holds more
+    /// values than `lower`.
+    upper: BinaryHeap<Reverse<Ordered<T>>>,
+    counts: BTreeMap<Ordered<T>, usize>,
+    max_count: usize,
+}
+
+impl<T: Number> RunningStats<T> {
+    pub fn new() -> Self {
+        RunningStats {
+            count: 0,
+            mean: 0.0,
+            m2: 0.0,
+            lower: BinaryHeap::new(),
+            upper: BinaryHeap::new(),
+            counts: BTreeMap::new(),
+            max_count: 0,
+        }
+    }
+
+    pub fn push(&mut self, x: T) {
+        self.count += 1;
+        let value = x.to_f64();
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+
+        match self.lower.peek() {
+            Some(top) if Ordered(x) > *top => self.upper.push(Reverse(Ordered(x))),
+            _ => self.lower.push(Ordered(x)),
+        }
+        if self.lower.len() > self.upper.len() + 1 {
+            let moved = self.lower.pop().unwrap();
+            self.upper.push(Reverse(moved));
+        } else if self.upper.len() > self.lower.len() {
+            let Reverse(moved) = self.upper.pop().unwrap();
+            self.lower.push(moved);
+        }
+
+        let count = self.counts.entry(Ordered(x)).or_insert(0);
+        *count += 1;
+        self.max_count = self.max_count.max(*count);
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.mean)
+    }
+
+    /// Population variance of everything pushed so far.
+    pub fn variance(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.m2 / self.count as f64)
+    }
+
+    pub fn std_dev(&self) -> Option<f64> {
+        self.variance().map(f64::sqrt)
+    }
+
+    pub fn median(&self) -> Option<f64> {
+        let Ordered(low) = *self.lower.peek()?;
+        match self.upper.peek() {
+            Some(Reverse(Ordered(high))) if self.lower.len() == self.upper.len() => {
+                Some((low.to_f64() + high.to_f64()) / 2.0)
+            }
+            _ => Some(low.to_f64()),
+        }
+    }
+
+    /// Every value seen most often so far, in ascending order.
+    pub fn modes(&self) -> Vec<T> {
+        self.counts
+            .iter()
+            .filter(|(_, count)| **count == self.max_count)
+            .map(|(Ordered(value), _)| *value)
+            .collect()
+    }
+
+    pub fn min(&self) -> Option<T> {
+        self.counts.keys().next().map(|Ordered(value)| *value)
+    }
+
+    pub fn max(&self) -> Option<T> {
+        self.counts.keys().next_back().map(|Ordered(value)| *value)
+    }
+
+    pub fn range(&self) -> Option<f64> {
+        Some(self.max()?.to_f64() - self.min()?.to_f64())
+    }
+}
+
+impl<T: Number> Default for RunningStats<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Number> Extend<T> for RunningStats<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for x in iter {
+            self.push(x);
+        }
+    }
+}
+
+impl<T: Number> FromIterator<T> for RunningStats<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut stats = RunningStats::new();
+        stats.extend(iter);
+        stats
+    }
+}
+
+impl<T: Number> fmt::Display for RunningStats<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (Some(mean), Some(median), Some(min), Some(max)) =
+            (self.mean(), self.median(), self.min(), self.max())
+        else {
+            return write!(f, "count    : 0");
+        };
+        let modes: Vec<String> = self.modes().iter().map(|m| m.to_string()).collect();
+        writeln!(f, "count    : {}", self.count())?;
+        writeln!(f, "mean     : {}", mean)?;
+        writeln!(f, "median   : {}", median)?;
+        writeln!(f, "mode     : {}", modes.join(", "))?;
+        writeln!(f, "min      : {}", min)?;
+        writeln!(f, "max      : {}", max)?;
+        writeln!(f, "range    : {}", self.range().unwrap_or(0.0))?;
+        writeln!(f, "variance : {}", self.variance().unwrap_or(0.0))?;
+        write!(f, "std dev  : {}", self.std_dev().unwrap_or(0.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats;
+
+    fn close(a: Option<f64>, b: Option<f64>) -> bool {
+        match (a, b) {
+            (Some(a), Some(b)) => (a - b).abs() < 1e-9,
+            (a, b) => a == b,
+        }
+    }
+
+    #[test]
+    fn empty_stream() {
+        let stats: RunningStats<i32> = RunningStats::new();
+        assert_eq!(stats.count(), 0);
+        assert_eq!(stats.mean(), None);
+        assert_eq!(stats.median(), None);
+        assert!(stats.modes().is_empty());
+        assert_eq!(stats.range(), None);
+        assert_eq!(stats.to_string(), "count    : 0");
+    }
+
+    #[test]
+    fn matches_batch_statistics_at_every_step() {
+        let data = [12, -3, 7, 7, 0, 25, -3, 7, 18, -3, 4, 9];
+        let mut running = RunningStats::new();
+        for (i, &x) in data.iter().enumerate() {
+            running.push(x);
+            let seen = &data[..=i];
+            assert!(close(running.mean(), stats::mean(seen)));
+            assert!(close(running.variance(), stats::variance(seen)));
+            assert!(close(running.median(), stats::median(seen)));
+            assert_eq!(running.modes(), stats::modes(seen));
+            assert_eq!(running.range(), stats::range(seen));
+        }
+    }
+
+    #[test]
+    fn floats_and_collect() {
+        let running: RunningStats<f64> = [0.5, 2.5, 1.5, 2.5].into_iter().collect();
+        assert_eq!(running.median(), Some(2.0));
+        assert_eq!(running.modes(), vec![2.5]);
+        assert_eq!((running.min(), running.max()), (Some(0.5), Some(2.5)));
+    }
+}
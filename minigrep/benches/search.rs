@@ -0,0 +1,69 @@
+//!
+//! Compare the memory-mapped input path with the plain `read_to_string`
+//! one on a file well above `MMAP_THRESHOLD`: loading the input on its
+//! own, loading it and walking its lines the way a search does but
+//! without matching, and a full search.
+//!
+//! cargo bench --bench search
+//!
+
+use std::{env, fs, process};
+
+use criterion::{Criterion, black_box, criterion_group, criterion_main};
+use minigrep::{Matcher, Source, search_hits};
+
+/// Size of the generated input.
+const INPUT_BYTES: usize = 64 * 1024 * 1024;
+
+fn bench_input(c: &mut Criterion) {
+    let poem = fs::read_to_string("poem.txt").unwrap();
+    let path = env::temp_dir().join(format!("minigrep-bench-{}.txt", process::id()));
+    fs::write(&path, poem.repeat(INPUT_BYTES / poem.len())).unwrap();
+
+    let mut group = c.benchmark_group("load 64 MiB");
+    group.sample_size(20);
+    group.bench_function("read_to_string", |b| {
+        b.iter(|| black_box(fs::read_to_string(&path).unwrap().len()))
+    });
+    group.bench_function("mmap", |b| {
+        b.iter(|| black_box(Source::open(&path, true).unwrap().as_str().len()))
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("lines 64 MiB");
+    group.sample_size(20);
+    group.bench_function("read_to_string", |b| {
+        b.iter(|| black_box(fs::read_to_string(&path).unwrap().lines().count()))
+    });
+    group.bench_function("mmap", |b| {
+        b.iter(|| {
+            let source = Source::open(&path, true).unwrap();
+            black_box(source.as_str().lines().count())
+        })
+    });
+    group.finish();
+
+    // a query on every ninth line, and one that never matches
+    for query in ["somebody", "nightingale"] {
+        let matcher = Matcher::new(query);
+        let mut group = c.benchmark_group(format!("search {} 64 MiB", query));
+        group.sample_size(20);
+        group.bench_function("read_to_string", |b| {
+            b.iter(|| {
+                let contents = fs::read_to_string(&path).unwrap();
+                black_box(search_hits(&matcher, &contents).len())
+            })
+        });
+        group.bench_function("mmap", |b| {
+            b.iter(|| {
+                let source = Source::open(&path, true).unwrap();
+                black_box(search_hits(&matcher, source.as_str()).len())
+            })
+        });
+        group.finish();
+    }
+    fs::remove_file(&path).unwrap();
+}
+
+criterion_group!(benches, bench_input);
+criterion_main!(benches);
@@ -0,0 +1,456 @@
+use std::fmt;
+use std::io::IsTerminal;
+use std::path::PathBuf;
+use std::{env, fs, io};
+
+use crate::files::SortKey;
+use crate::report::Format;
+use crate::{Error, Matcher};
+
+/// When to highlight matches with ANSI colours.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorChoice {
+    /// Only when stdout is a terminal.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "auto" => Some(ColorChoice::Auto),
+            "always" => Some(ColorChoice::Always),
+            "never" => Some(ColorChoice::Never),
+            _ => None,
+        }
+    }
+
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Auto => io::stdout().is_terminal(),
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
+    }
+}
+
+impl fmt::Display for ColorChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ColorChoice::Auto => "auto",
+            ColorChoice::Always => "always",
+            ColorChoice::Never => "never",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Everything minigrep needs to know to run a search.
+///
+/// Settings are layered: built-in defaults, then the config file (see
+/// [`Config::config_path`]), then the `CASE_INSENSITIVE` environment
+/// variable, and finally the command line, so a flag always wins.
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    pub query: String,
+    pub filename: String,
+    pub case_insensitive: bool,
+    /// `-S`: ignore case unless the query contains an uppercase letter.
+    pub smart_case: bool,
+    /// `-w`: only match whole words.
+    pub whole_word: bool,
+    /// `-x`: the whole line must equal the query.
+    pub whole_line: bool,
+    /// `-q`: print nothing, only report through the exit status.
+    pub quiet: bool,
+    pub color: ColorChoice,
+    /// `--tui`: browse the results interactively.
+    pub tui: bool,
+    /// `--no-mmap`: never memory-map the input, always read it into memory.
+    pub no_mmap: bool,
+    /// `--stats`: report search statistics after the results.
+    pub stats: bool,
+    /// `-b`: also print the byte offset of each hit.
+    pub byte_offset: bool,
+    /// `--format`: plain text, or an HTML / Markdown report.
+    pub format: Format,
+    /// `--sort KEY` / `--sortr KEY`: search files in a stable order.
+    pub sort: Option<SortKey>,
+    /// Set by `--sortr`: reverse the sort order.
+    pub sort_reverse: bool,
+    /// `--unique`: print each distinct matched line only once.
+    pub unique: bool,
+    /// `ignore-dirs` in the config file: names of directories not to
+    /// descend into when searching a directory, e.g. `.git, target`.
+    pub ignore_dirs: Vec<String>,
+    /// `--print-config`: show the effective settings instead of searching.
+    pub print_config: bool,
+    /// The config file the defaults were read from, if any.
+    pub config_file: Option<PathBuf>,
+}
+
+impl Config {
+    /// Parse `minigrep [OPTIONS] QUERY FILENAME`, merging the config file
+    /// underneath the command line unless `--no-config` is given. Short
+    /// flags may be grouped (`-wx`) and may appear anywhere before `--`;
+    /// a query starting with `-` is given after `--` or with `-e`.
+    pub fn new(args: &[String]) -> Result<Self, Error> {
+        let mut cfg = Config::default();
+        let options = args.iter().take_while(|arg| *arg != "--");
+        if !options.into_iter().any(|arg| arg == "--no-config") {
+            cfg.load_config_file()?;
+        }
+        if env::var("CASE_INSENSITIVE").is_ok() {
+            cfg.case_insensitive = true;
+        }
+        cfg.apply_args(args)?;
+        Ok(cfg)
+    }
+
+    /// The config file to read defaults from: `$MINIGREP_CONFIG` if set,
+    /// otherwise `$XDG_CONFIG_HOME/minigrep/config` or
+    /// `~/.config/minigrep/config`.
+    pub fn config_path() -> Option<PathBuf> {
+        if let Some(path) = env::var_os("MINIGREP_CONFIG") {
+            return Some(PathBuf::from(path));
+        }
+        let base = env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(base.join("minigrep").join("config"))
+    }
+
+    fn load_config_file(&mut self) -> Result<(), Error> {
+        let Some(path) = Config::config_path() else {
+            return Ok(());
+        };
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            // a missing file at the standard location just means "no defaults"
+            Err(e)
+                if e.kind() == io::ErrorKind::NotFound
+                    && env::var_os("MINIGREP_CONFIG").is_none() =>
+            {
+                return Ok(());
+            }
+            Err(source) => {
+                return Err(Error::Io {
+                    path: path.display().to_string(),
+                    source,
+                });
+            }
+        };
+        self.apply_config_text(&text)
+            .map_err(|(line, msg)| Error::Config {
+                path: path.display().to_string(),
+                line,
+                msg,
+            })?;
+        self.config_file = Some(path);
+        Ok(())
+    }
+
+    /// Apply `key = value` lines; `#` starts a comment. On failure returns
+    /// the 1-based line number and what was wrong with it.
+    pub fn apply_config_text(&mut self, text: &str) -> Result<(), (usize, String)> {
+        for (idx, line) in (1..).zip(text.lines()) {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                return Err((idx, format!("expected `key = value`, found `{}`", line)));
+            };
+            self.apply_setting(key.trim(), value.trim())
+                .map_err(|msg| (idx, msg))?;
+        }
+        Ok(())
+    }
+
+    fn apply_setting(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let flag = || match value {
+            "true" => Ok(true),
+            "false" => Ok(false),
+            _ => Err(format!(
+                "`{}` expects true or false, found `{}`",
+                key, value
+            )),
+        };
+        match key {
+            "ignore-case" => self.case_insensitive = flag()?,
+            "smart-case" => self.smart_case = flag()?,
+            "word-regexp" => self.whole_word = flag()?,
+            "line-regexp" => self.whole_line = flag()?,
+            "ignore-dirs" => {
+                self.ignore_dirs = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                    .map(String::from)
+                    .collect();
+            }
+            "color" => {
+                self.color = ColorChoice::parse(value).ok_or_else(|| {
+                    format!("`color` expects auto, always or never, found `{}`", value)
+                })?;
+            }
+            _ => return Err(format!("unknown setting `{}`", key)),
+        }
+        Ok(())
+    }
+
+    fn apply_args(&mut self, args: &[String]) -> Result<(), Error> {
+        let mut positional = Vec::new();
+        let mut pattern = None;
+        let mut args = args.iter().skip(1);
+        while let Some(arg) = args.next() {
+            if arg == "--" {
+                positional.extend(args.cloned());
+                break;
+            }
+            // `--name=value` and `--name value` mean the same thing
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) if arg.starts_with("--") => (flag, Some(value.to_string())),
+                _ => (arg.as_str(), None),
+            };
+            let mut value = || {
+                inline
+                    .clone()
+                    .or_else(|| args.next().cloned())
+                    .ok_or_else(|| Error::Usage(format!("{} needs a value", flag)))
+            };
+            match flag {
+                "--ignore-case" => self.case_insensitive = true,
+                "--case-sensitive" => {
+                    self.case_insensitive = false;
+                    self.smart_case = false;
+                }
+                "--smart-case" => self.smart_case = true,
+                "--word-regexp" => self.whole_word = true,
+                "--line-regexp" => self.whole_line = true,
+                "--quiet" => self.quiet = true,
+                "--byte-offset" => self.byte_offset = true,
+                "--tui" => self.tui = true,
+                "--no-mmap" => self.no_mmap = true,
+                "--stats" => self.stats = true,
+                "--unique" => self.unique = true,
+                "--no-config" => {}
+                "--print-config" => self.print_config = true,
+                "--color" => self.set_color(&value()?)?,
+                "--format" => self.set_format(&value()?)?,
+                "--sort" => self.set_sort(&value()?, false)?,
+                "--sortr" => self.set_sort(&value()?, true)?,
+                "-e" => pattern = Some(value()?),
+                flag if flag.starts_with("--") => {
+                    return Err(Error::Usage(format!("Unknown option {}", flag)));
+                }
+                flags if flags.starts_with('-') && flags.len() > 1 => {
+                    for c in flags.chars().skip(1) {
+                        match c {
+                            'i' => self.case_insensitive = true,
+                            's' => {
+                                self.case_insensitive = false;
+                                self.smart_case = false;
+                            }
+                            'S' => self.smart_case = true,
+                            'w' => self.whole_word = true,
+                            'x' => self.whole_line = true,
+                            'q' => self.quiet = true,
+                            'b' => self.byte_offset = true,
+                            _ => return Err(Error::Usage(format!("Unknown option -{}", c))),
+                        }
+                    }
+                }
+                _ => positional.push(arg.clone()),
+            }
+        }
+        // with `-e` every positional argument is a file
+        let mut positional = pattern.into_iter().chain(positional);
+        match (positional.next(), positional.next()) {
+            (Some(query), Some(filename)) => {
+                self.query = query;
+                self.filename = filename;
+            }
+            _ if self.print_config => {}
+            _ => return Err(Error::Usage("Not enough arguments".to_string())),
+        }
+        Ok(())
+    }
+
+    fn set_color(&mut self, value: &str) -> Result<(), Error> {
+        self.color = ColorChoice::parse(value).ok_or_else(|| {
+            Error::Usage(format!(
+                "--color expects auto, always or never, found {}",
+                value
+            ))
+        })?;
+        Ok(())
+    }
+
+    fn set_sort(&mut self, value: &str, reverse: bool) -> Result<(), Error> {
+        let key = SortKey::parse(value).ok_or_else(|| {
+            Error::Usage(format!(
+                "--sort expects path, modified or created, found {}",
+                value
+            ))
+        })?;
+        self.sort = Some(key);
+        self.sort_reverse = reverse;
+        Ok(())
+    }
+
+    fn set_format(&mut self, value: &str) -> Result<(), Error> {
+        self.format = Format::parse(value).ok_or_else(|| {
+            Error::Usage(format!(
+                "--format expects text, html or markdown, found {}",
+                value
+            ))
+        })?;
+        Ok(())
+    }
+
+    /// Whether the search ignores case once smart case has been resolved
+    /// against the query.
+    pub fn ignores_case(&self) -> bool {
+        self.case_insensitive || (self.smart_case && !self.query.chars().any(char::is_uppercase))
+    }
+
+    pub fn matcher(&self) -> Matcher {
+        Matcher::new(&self.query)
+            .ignore_case(self.ignores_case())
+            .whole_word(self.whole_word)
+            .whole_line(self.whole_line)
+    }
+}
+
+/// Prints the effective settings in config file syntax, so the output of
+/// `--print-config` can be saved as a starting point.
+impl fmt::Display for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.config_file {
+            Some(path) => writeln!(f, "# loaded from {}", path.display())?,
+            None => writeln!(f, "# no config file loaded")?,
+        }
+        writeln!(f, "ignore-case = {}", self.case_insensitive)?;
+        writeln!(f, "smart-case = {}", self.smart_case)?;
+        writeln!(f, "word-regexp = {}", self.whole_word)?;
+        writeln!(f, "line-regexp = {}", self.whole_line)?;
+        writeln!(f, "ignore-dirs = {}", self.ignore_dirs.join(", "))?;
+        writeln!(f, "color = {}", self.color)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn config_text_sets_defaults() {
+        let mut cfg = Config::default();
+        cfg.apply_config_text(
+            "# team defaults\nsmart-case = true\ncolor = never # no escapes\n\nignore-dirs = .git, target,\n",
+        )
+        .unwrap();
+        assert!(cfg.smart_case);
+        assert_eq!(ColorChoice::Never, cfg.color);
+        assert_eq!(vec![".git", "target"], cfg.ignore_dirs);
+    }
+
+    #[test]
+    fn config_text_reports_line_of_bad_setting() {
+        let mut cfg = Config::default();
+        let err = cfg
+            .apply_config_text("color = never\nsmart-case = yes\n")
+            .unwrap_err();
+        assert_eq!(2, err.0);
+        let err = cfg.apply_config_text("colour = never\n").unwrap_err();
+        assert_eq!((1, "unknown setting `colour`".to_string()), err);
+    }
+
+    #[test]
+    fn flags_override_config_file() {
+        let mut cfg = Config::default();
+        cfg.apply_config_text("ignore-case = true\ncolor = always")
+            .unwrap();
+        cfg.apply_args(&args(&[
+            "minigrep",
+            "-s",
+            "--color=never",
+            "Rust",
+            "poem.txt",
+        ]))
+        .unwrap();
+        assert!(!cfg.ignores_case());
+        assert_eq!(ColorChoice::Never, cfg.color);
+    }
+
+    #[test]
+    fn options_take_values_inline_or_separately() {
+        let mut cfg = Config::default();
+        cfg.apply_args(&args(&[
+            "minigrep",
+            "--sortr",
+            "modified",
+            "--format=md",
+            "x",
+            "src",
+        ]))
+        .unwrap();
+        assert_eq!(Some(SortKey::Modified), cfg.sort);
+        assert!(cfg.sort_reverse);
+        assert_eq!(Format::Markdown, cfg.format);
+        assert!(matches!(
+            cfg.apply_args(&args(&["minigrep", "x", "src", "--sort"])),
+            Err(Error::Usage(msg)) if msg == "--sort needs a value"
+        ));
+    }
+
+    #[test]
+    fn double_dash_ends_the_options() {
+        let mut cfg = Config::default();
+        cfg.apply_args(&args(&["minigrep", "-w", "--", "-x", "--stats"]))
+            .unwrap();
+        assert_eq!("-x", cfg.query);
+        assert_eq!("--stats", cfg.filename);
+        assert!(cfg.whole_word && !cfg.whole_line && !cfg.stats);
+    }
+
+    #[test]
+    fn dash_e_gives_the_query() {
+        let mut cfg = Config::default();
+        cfg.apply_args(&args(&["minigrep", "poem.txt", "-e", "-x"]))
+            .unwrap();
+        assert_eq!("-x", cfg.query);
+        assert_eq!("poem.txt", cfg.filename);
+        assert!(!cfg.whole_line);
+        assert!(matches!(
+            cfg.apply_args(&args(&["minigrep", "-e"])),
+            Err(Error::Usage(msg)) if msg == "-e needs a value"
+        ));
+    }
+
+    #[test]
+    fn smart_case() {
+        let mut cfg = Config::default();
+        cfg.apply_args(&args(&["minigrep", "-S", "rust", "poem.txt"]))
+            .unwrap();
+        assert!(cfg.ignores_case());
+        cfg.query = "Rust".to_string();
+        assert!(!cfg.ignores_case());
+    }
+
+    #[test]
+    fn print_config_needs_no_query() {
+        let mut cfg = Config::default();
+        cfg.apply_args(&args(&["minigrep", "--print-config", "--no-config"]))
+            .unwrap();
+        assert!(cfg.print_config);
+        assert!(cfg.to_string().contains("color = auto"));
+    }
+}
@@ -0,0 +1,44 @@
+use std::{fmt, io};
+
+/// Everything that can make minigrep fail. Any of these ends the process
+/// with exit status 2, the same way grep reports trouble.
+#[derive(Debug)]
+pub enum Error {
+    /// The command line could not be understood.
+    Usage(String),
+    /// A file could not be read.
+    Io { path: String, source: io::Error },
+    /// The config file contains a setting minigrep does not understand.
+    Config {
+        path: String,
+        line: usize,
+        msg: String,
+    },
+    /// Nothing matched, and this many files or directories met while
+    /// walking a directory could not be read.
+    Skipped(usize),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Usage(msg) => write!(f, "{}", msg),
+            Error::Io { path, source } => write!(f, "{}: {}", path, source),
+            Error::Config { path, line, msg } => write!(f, "{}:{}: {}", path, line, msg),
+            Error::Skipped(count) => write!(
+                f,
+                "some files or directories could not be read ({} skipped)",
+                count
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Usage(_) | Error::Config { .. } | Error::Skipped(_) => None,
+            Error::Io { source, .. } => Some(source),
+        }
+    }
+}
@@ -0,0 +1,257 @@
+//!
+//! Turning the path on the command line into the list of files to search,
+//! and putting that list into a reproducible order.
+//!
+
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// What `--sort` / `--sortr` order the searched files by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    Path,
+    Modified,
+    Created,
+}
+
+impl SortKey {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "path" => Some(SortKey::Path),
+            "modified" => Some(SortKey::Modified),
+            "created" => Some(SortKey::Created),
+            _ => None,
+        }
+    }
+}
+
+/// The files to search for `path`: the path itself for a file (or `-` for
+/// stdin), every file below it for a directory. Directory entries come back
+/// in whatever order the filesystem lists them.
+///
+/// Symlinks are followed, but a directory already visited is not walked
+/// again, so a link back up the tree cannot loop. Entries that are neither
+/// files nor directories (sockets, FIFOs) are left out; a dangling link is
+/// kept so the caller can report it.
+pub fn collect_files(
+    path: &str,
+    ignore_dirs: &[String],
+    skipped: &mut Vec<(PathBuf, io::Error)>,
+) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let path = Path::new(path);
+    if path != Path::new("-") && fs::metadata(path)?.is_dir() {
+        let mut walker = Walker {
+            ignore_dirs,
+            visited: HashSet::new(),
+            files: &mut files,
+            skipped,
+        };
+        walker.walk(path)?;
+    } else {
+        files.push(path.to_path_buf());
+    }
+    Ok(files)
+}
+
+struct Walker<'a> {
+    ignore_dirs: &'a [String],
+    visited: HashSet<PathBuf>,
+    files: &'a mut Vec<PathBuf>,
+    skipped: &'a mut Vec<(PathBuf, io::Error)>,
+}
+
+impl Walker<'_> {
+    /// Collect the files under `dir`. Only an error about `dir` itself is
+    /// returned; the directories below it that cannot be read are noted in
+    /// `skipped` and the walk goes on.
+    fn walk(&mut self, dir: &Path) -> io::Result<()> {
+        if !self.visited.insert(fs::canonicalize(dir)?) {
+            return Ok(());
+        }
+        for entry in fs::read_dir(dir)? {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    self.skipped.push((dir.to_path_buf(), e));
+                    continue;
+                }
+            };
+            let path = entry.path();
+            match fs::metadata(&path) {
+                Ok(metadata) if metadata.is_dir() => {
+                    let ignored = self
+                        .ignore_dirs
+                        .iter()
+                        .any(|name| entry.file_name() == name.as_str());
+                    if !ignored && let Err(e) = self.walk(&path) {
+                        self.skipped.push((path, e));
+                    }
+                }
+                Ok(metadata) if metadata.is_file() => self.files.push(path),
+                Ok(_) => {}
+                Err(_) => self.files.push(path),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Sort `files` by `key`, newest last for the time keys. Files whose
+/// timestamp the platform cannot report sort first, then by path.
+pub fn sort_files(files: &mut [PathBuf], key: SortKey, reverse: bool) {
+    let stamp = |path: &Path| -> Option<SystemTime> {
+        match key {
+            SortKey::Path => None,
+            SortKey::Modified => fs::metadata(path).ok()?.modified().ok(),
+            SortKey::Created => fs::metadata(path).ok()?.created().ok(),
+        }
+    };
+    let mut keyed: Vec<(Option<SystemTime>, PathBuf)> = files
+        .iter()
+        .map(|path| (stamp(path), path.clone()))
+        .collect();
+    keyed.sort_by(|a, b| {
+        let order = a.0.cmp(&b.0).then_with(|| a.1.cmp(&b.1));
+        if reverse { order.reverse() } else { order }
+    });
+    for (slot, (_, path)) in files.iter_mut().zip(keyed) {
+        *slot = path;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, process};
+
+    #[test]
+    fn walks_directories_and_sorts_by_path() {
+        let root = env::temp_dir().join(format!("minigrep-walk-{}", process::id()));
+        fs::create_dir_all(root.join("b")).unwrap();
+        fs::write(root.join("b").join("z.txt"), "").unwrap();
+        fs::write(root.join("c.txt"), "").unwrap();
+        fs::write(root.join("a.txt"), "").unwrap();
+
+        let mut files = collect_files(root.to_str().unwrap(), &[], &mut Vec::new()).unwrap();
+        sort_files(&mut files, SortKey::Path, false);
+        let names: Vec<_> = files
+            .iter()
+            .map(|path| path.strip_prefix(&root).unwrap().to_path_buf())
+            .collect();
+        assert_eq!(
+            vec![
+                PathBuf::from("a.txt"),
+                Path::new("b").join("z.txt"),
+                PathBuf::from("c.txt")
+            ],
+            names
+        );
+
+        sort_files(&mut files, SortKey::Path, true);
+        assert!(files[0].ends_with("c.txt"));
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn follows_symlinks_without_looping() {
+        use std::os::unix::fs::symlink;
+
+        let root = env::temp_dir().join(format!("minigrep-links-{}", process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::write(root.join("sub").join("a.txt"), "").unwrap();
+        symlink(root.join("sub"), root.join("linked")).unwrap();
+        symlink(&root, root.join("sub").join("up")).unwrap();
+        symlink(root.join("missing"), root.join("dangling")).unwrap();
+
+        let mut files = collect_files(root.to_str().unwrap(), &[], &mut Vec::new()).unwrap();
+        sort_files(&mut files, SortKey::Path, false);
+        let names: Vec<_> = files
+            .iter()
+            .map(|path| path.strip_prefix(&root).unwrap().to_path_buf())
+            .collect();
+        // `sub` is reached once, through whichever name is listed first
+        assert_eq!(names.len(), 2);
+        assert_eq!(names[0], PathBuf::from("dangling"));
+        assert!(names[1].ends_with("a.txt"));
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn skips_ignored_directories() {
+        let root = env::temp_dir().join(format!("minigrep-ignore-{}", process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("target").join("debug")).unwrap();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("target").join("debug").join("out.txt"), "").unwrap();
+        fs::write(root.join("src").join("target"), "").unwrap();
+
+        let mut files = collect_files(
+            root.to_str().unwrap(),
+            &["target".to_string()],
+            &mut Vec::new(),
+        )
+        .unwrap();
+        sort_files(&mut files, SortKey::Path, false);
+        // only directories are skipped, not files with the same name
+        assert_eq!(vec![root.join("src").join("target")], files);
+        // nor the directory that was asked for
+        let inside = root.join("target");
+        assert_eq!(
+            1,
+            collect_files(
+                inside.to_str().unwrap(),
+                &["target".to_string()],
+                &mut Vec::new()
+            )
+            .unwrap()
+            .len()
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn skips_unreadable_directories() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = env::temp_dir().join(format!("minigrep-locked-{}", process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("deep").join("locked")).unwrap();
+        fs::write(root.join("deep").join("locked").join("a.txt"), "").unwrap();
+        fs::write(root.join("b.txt"), "").unwrap();
+        let locked = root.join("deep").join("locked");
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).unwrap();
+
+        let mut skipped = Vec::new();
+        let files = collect_files(root.to_str().unwrap(), &[], &mut skipped);
+        let readable = fs::read_dir(&locked).is_ok();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+        // root can read it anyway
+        if readable {
+            return;
+        }
+        assert_eq!(vec![root.join("b.txt")], files.unwrap());
+        assert_eq!(1, skipped.len());
+        assert_eq!(locked, skipped[0].0);
+    }
+
+    #[test]
+    fn single_file_and_stdin_pass_through() {
+        assert_eq!(
+            vec![PathBuf::from("poem.txt")],
+            collect_files("poem.txt", &[], &mut Vec::new()).unwrap()
+        );
+        assert_eq!(
+            vec![PathBuf::from("-")],
+            collect_files("-", &[], &mut Vec::new()).unwrap()
+        );
+        assert!(collect_files("missing.txt", &[], &mut Vec::new()).is_err());
+    }
+}
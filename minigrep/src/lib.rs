@@ -1,66 +1,233 @@
-use std::error::Error;
-use std::{env, fs};
+use std::collections::HashSet;
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+use std::time::Instant;
 
-pub struct Config {
-    pub query: String,
-    pub filename: String,
-    pub case_insensitive: bool,
+mod config;
+mod error;
+pub mod files;
+mod matcher;
+pub mod report;
+pub mod source;
+mod stats;
+pub mod tui;
+pub use config::{ColorChoice, Config};
+pub use error::Error;
+pub use files::SortKey;
+pub use matcher::Matcher;
+pub use report::Format;
+pub use source::Source;
+pub use stats::Stats;
+
+/// A matching line together with where the query matched in it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hit<'a> {
+    /// 1-based line number within the searched text.
+    pub line_number: usize,
+    /// 0-based byte offset of the start of `line` within the searched text.
+    pub line_offset: usize,
+    pub line: &'a str,
+    /// Byte ranges of every match within `line`.
+    pub spans: Vec<Range<usize>>,
 }
 
-impl Config {
-    pub fn new(args: &Vec<String>) -> Result<Self, &str> {
-        if args.len() < 3 {
-            return Err("Not enough arguments");
-        }
-        Ok(Self {
-            query: args[1].clone(),
-            filename: args[2].clone(),
-            case_insensitive: env::var("CASE_INSENSITIVE").is_err(),
-        })
+impl Hit<'_> {
+    /// 1-based column of the first match, counted in characters.
+    pub fn column(&self) -> usize {
+        let start = self.spans.first().map_or(0, |span| span.start);
+        self.line[..start].chars().count() + 1
     }
+
+    /// 0-based byte offset of the first match within the searched text.
+    pub fn byte_offset(&self) -> usize {
+        self.line_offset + self.spans.first().map_or(0, |span| span.start)
+    }
+}
+
+/// Like `str::lines`, but also yields the byte offset where each line
+/// starts, so positions can be reported relative to the whole text.
+fn lines_with_offsets(contents: &str) -> impl Iterator<Item = (usize, &str)> {
+    contents.split_inclusive('\n').scan(0, |offset, raw| {
+        let start = *offset;
+        *offset += raw.len();
+        let line = raw.strip_suffix('\n').unwrap_or(raw);
+        Some((start, line.strip_suffix('\r').unwrap_or(line)))
+    })
+}
+
+/// Return a [`Hit`] for every line of `contents` accepted by `matcher`.
+pub fn search_hits<'a>(matcher: &Matcher, contents: &'a str) -> Vec<Hit<'a>> {
+    (1..)
+        .zip(lines_with_offsets(contents))
+        .filter_map(|(line_number, (line_offset, line))| {
+            let spans = matcher.find_iter(line);
+            (!spans.is_empty()).then_some(Hit {
+                line_number,
+                line_offset,
+                line,
+                spans,
+            })
+        })
+        .collect()
+}
+
+/// Return every line of `contents` accepted by `matcher`.
+pub fn search_with<'a>(matcher: &Matcher, contents: &'a str) -> Vec<&'a str> {
+    contents
+        .lines()
+        .filter(|line| matcher.is_match(line))
+        .collect()
 }
 
 pub fn search<'a>(query: &str, contents: &'a str) -> Vec<&'a str> {
-    let mut results = Vec::new();
-    for line in contents.lines() {
-        if line.contains(query) {
-            results.push(line);
-        }
-    }
-    results
+    search_with(&Matcher::new(query), contents)
 }
 
 pub fn search_case_insensitive<'a>(query: &str, contents: &'a str) -> Vec<&'a str> {
-    let query = query.to_lowercase();
-    let mut results = Vec::new();
-    for line in contents.lines() {
-        if line.to_lowercase().contains(&query) {
-            results.push(line);
-        }
-    }
-    results
+    search_with(&Matcher::new(query).ignore_case(true), contents)
 }
 
-pub fn run(cfg: Config) -> Result<(), Box<dyn Error>> {
-    println!("Searching for {}", cfg.query);
-    let contents = fs::read_to_string(cfg.filename)?;
-    // println!("With text:\n{}", contents);
-    let mut idx = 1;
-    let results = if cfg.case_insensitive {
-        search(&cfg.query, &contents)
-    } else {
-        search_case_insensitive(&cfg.query, &contents)
+/// Search `cfg.filename` and print the matching lines. A directory is
+/// searched recursively; `--sort` fixes the order its files are visited in
+/// and `--unique` drops lines already printed for an earlier file.
+///
+/// Returns `Ok(true)` when at least one line matched and `Ok(false)` when
+/// nothing did, so the caller can map the outcome to grep's exit statuses.
+/// Files and directories that could not be read are skipped with a
+/// warning; when nothing matched they make the run an `Error::Skipped`.
+pub fn run(cfg: Config) -> Result<bool, Error> {
+    let started = Instant::now();
+    let mut skipped = Vec::new();
+    let mut paths =
+        files::collect_files(&cfg.filename, &cfg.ignore_dirs, &mut skipped).map_err(|source| {
+            Error::Io {
+                path: cfg.filename.clone(),
+                source,
+            }
+        })?;
+    // directories that could not be walked are reported like files that
+    // could not be read, and make the run fail when nothing matched
+    for (path, e) in &skipped {
+        eprintln!("minigrep: {}: {} (skipped)", path.display(), e);
+    }
+    let mut failed = skipped.len();
+    let walked = paths.len() != 1 || paths[0] != Path::new(&cfg.filename);
+    if let Some(key) = cfg.sort {
+        files::sort_files(&mut paths, key, cfg.sort_reverse);
+    }
+    let mut inputs = Vec::new();
+    for path in paths {
+        match Source::open(&path, !cfg.no_mmap) {
+            Ok(source) => inputs.push((path.display().to_string(), source)),
+            // binary files met while walking a directory are skipped
+            Err(e) if walked && e.kind() == io::ErrorKind::InvalidData => {}
+            // so are the ones that cannot be read, with a warning
+            Err(e) if walked => {
+                eprintln!("minigrep: {}: {} (skipped)", path.display(), e);
+                failed += 1;
+            }
+            Err(source) => {
+                return Err(Error::Io {
+                    path: path.display().to_string(),
+                    source,
+                });
+            }
+        }
+    }
+    if cfg.tui {
+        let [(name, input)] = inputs.as_slice() else {
+            return Err(Error::Usage("--tui needs a single file".to_string()));
+        };
+        let picked = tui::browse(&cfg, input.as_str()).map_err(|source| Error::Io {
+            path: "terminal".to_string(),
+            source,
+        })?;
+        if let Some(line) = picked {
+            println!("{}:{}", name, line);
+        }
+        return Ok(picked.is_some());
+    }
+    let matcher = cfg.matcher();
+    let outcome = |matched: bool| match failed {
+        0 => Ok(matched),
+        _ if matched => Ok(true),
+        count => Err(Error::Skipped(count)),
     };
-    if results.len() == 0 {
-        println!("No results found.");
-        // eprintln!("No results found for query: {}", cfg.query);
-        return Ok(());
+    if cfg.quiet {
+        return outcome(
+            inputs
+                .iter()
+                .any(|(_, input)| input.as_str().lines().any(|line| matcher.is_match(line))),
+        );
     }
-    for line in results {
-        println!("{}: {}", idx, line);
-        idx += 1;
+    let mut seen = HashSet::new();
+    let results: Vec<(&str, Vec<Hit>)> = inputs
+        .iter()
+        .map(|(name, input)| {
+            let mut hits = search_hits(&matcher, input.as_str());
+            if cfg.unique {
+                hits.retain(|hit| seen.insert(hit.line));
+            }
+            (name.as_str(), hits)
+        })
+        .collect();
+    let matched = results.iter().any(|(_, hits)| !hits.is_empty());
+    if cfg.format == Format::Text {
+        println!("Searching for {}", cfg.query);
+        let color = cfg.color.enabled();
+        for (name, hits) in &results {
+            for hit in hits {
+                let mut position = format!("{}:{}:{}:", name, hit.line_number, hit.column());
+                if cfg.byte_offset {
+                    position.push_str(&format!("{}:", hit.byte_offset()));
+                }
+                if color {
+                    println!("{}{}", position, highlight(hit.line, &hit.spans));
+                } else {
+                    println!("{}{}", position, hit.line);
+                }
+            }
+        }
+    } else {
+        let files: Vec<(&str, &[Hit])> = results
+            .iter()
+            .map(|(name, hits)| (*name, hits.as_slice()))
+            .collect();
+        print!("{}", report::render(cfg.format, &files));
     }
-    Ok(())
+    // after the header, so the two streams read in order on a terminal
+    if !matched {
+        eprintln!("No results found.");
+    }
+    if cfg.stats {
+        let mut stats = Stats::default();
+        for (_, input) in &inputs {
+            stats.add_file(input.as_str().len());
+        }
+        for hit in results.iter().flat_map(|(_, hits)| hits) {
+            stats.add_line(hit.line, &hit.spans);
+        }
+        stats.elapsed = started.elapsed();
+        println!();
+        print!("{}", stats);
+    }
+    outcome(matched)
+}
+
+/// Wrap each matched span of `line` in bold red ANSI escapes.
+fn highlight(line: &str, spans: &[Range<usize>]) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut last = 0;
+    for span in spans {
+        out.push_str(&line[last..span.start]);
+        out.push_str("\x1b[1;31m");
+        out.push_str(&line[span.clone()]);
+        out.push_str("\x1b[0m");
+        last = span.end;
+    }
+    out.push_str(&line[last..]);
+    out
 }
 
 #[cfg(test)]
@@ -91,4 +258,63 @@ Trust me.
             search_case_insensitive(query, contents)
         )
     }
+    #[test]
+    fn parse_flags() {
+        let args: Vec<String> = ["minigrep", "-w", "id", "-x", "poem.txt", "--no-config"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let cfg = Config::new(&args).unwrap();
+        assert_eq!("id", cfg.query);
+        assert_eq!("poem.txt", cfg.filename);
+        assert!(cfg.whole_word && cfg.whole_line);
+
+        let args: Vec<String> = ["minigrep", "-wq", "id", "poem.txt", "--no-config"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert!(Config::new(&args).is_ok_and(|cfg| cfg.quiet));
+
+        let args: Vec<String> = ["minigrep", "-z", "id", "poem.txt", "--no-config"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert!(matches!(Config::new(&args), Err(Error::Usage(_))));
+    }
+    #[test]
+    fn hits_report_real_positions() {
+        let contents = "Rust:\r\nSafe, Fast, Productive.\r\nПривет, rust\n";
+        let hits = search_hits(&Matcher::new("rust").ignore_case(true), contents);
+        assert_eq!(
+            vec![1, 3],
+            hits.iter().map(|h| h.line_number).collect::<Vec<_>>()
+        );
+        assert_eq!(1, hits[0].column());
+        assert_eq!(0, hits[0].byte_offset());
+        assert_eq!("Привет, rust", hits[1].line);
+        assert_eq!(9, hits[1].column());
+        assert_eq!(32 + 14, hits[1].byte_offset());
+    }
+    #[test]
+    fn highlight_spans() {
+        assert_eq!(
+            "\x1b[1;31mT\x1b[0mr\x1b[1;31must\x1b[0m me.",
+            highlight("Trust me.", &[0..1, 2..5])
+        );
+    }
+    #[test]
+    fn run_reports_match_miss_and_failure() {
+        let cfg = |query: &str, filename: &str| Config {
+            query: query.to_string(),
+            filename: filename.to_string(),
+            quiet: true,
+            ..Default::default()
+        };
+        assert!(run(cfg("nobody", "poem.txt")).unwrap());
+        assert!(!run(cfg("xyzzy", "poem.txt")).unwrap());
+        assert!(matches!(
+            run(cfg("nobody", "missing.txt")),
+            Err(Error::Io { .. })
+        ));
+    }
 }
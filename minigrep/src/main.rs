@@ -1,18 +1,22 @@
-use std::env;
+use std::{env, process};
 
 fn main() {
     let args: Vec<String> = env::args().collect();
     let cfg = minigrep::Config::new(&args).unwrap_or_else(|err| {
-        // eprintln!("Problem parsing arguments: {}", err);
-        println!("Problem parsing arguments: {}", err);
-        std::process::exit(1);
+        eprintln!("Problem parsing arguments: {}", err);
+        process::exit(2);
     });
+    if cfg.print_config {
+        print!("{}", cfg);
+        return;
+    }
+    // grep-style exit statuses: 0 = a line matched, 1 = no match, 2 = error
     match minigrep::run(cfg) {
-        Ok(_) => {}
+        Ok(true) => {}
+        Ok(false) => process::exit(1),
         Err(e) => {
-            // eprintln!("Application error: {}", e);
-            println!("Application error: {}", e);
+            eprintln!("Application error: {}", e);
+            process::exit(2);
         }
     }
 }
-
@@ -0,0 +1,223 @@
+use std::ops::Range;
+
+/// The matching layer used by every search in minigrep.
+///
+/// A `Matcher` looks for a literal query inside a line and can be narrowed
+/// down with `ignore_case`, `whole_word` (`-w`) and `whole_line` (`-x`).
+/// The options combine freely, e.g. `-w` together with case-insensitive
+/// search only reports whole words regardless of their case.
+///
+/// # Examples
+/// ```
+/// use minigrep::Matcher;
+///
+/// let matcher = Matcher::new("id").whole_word(true);
+/// assert!(matcher.is_match("the id column"));
+/// assert!(!matcher.is_match("void identity width"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Matcher {
+    query: String,
+    ignore_case: bool,
+    whole_word: bool,
+    whole_line: bool,
+}
+
+impl Matcher {
+    pub fn new(query: &str) -> Self {
+        Self {
+            query: query.to_string(),
+            ignore_case: false,
+            whole_word: false,
+            whole_line: false,
+        }
+    }
+
+    pub fn ignore_case(mut self, yes: bool) -> Self {
+        self.ignore_case = yes;
+        self
+    }
+
+    /// Only match when the hit is surrounded by Unicode word boundaries.
+    pub fn whole_word(mut self, yes: bool) -> Self {
+        self.whole_word = yes;
+        self
+    }
+
+    /// Only match when the whole line equals the query.
+    pub fn whole_line(mut self, yes: bool) -> Self {
+        self.whole_line = yes;
+        self
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    pub fn is_match(&self, line: &str) -> bool {
+        self.find(line).is_some()
+    }
+
+    /// Byte range of the first match in `line`.
+    pub fn find(&self, line: &str) -> Option<Range<usize>> {
+        self.find_from(line, 0)
+    }
+
+    /// Byte ranges of all non-overlapping matches in `line`, left to right.
+    pub fn find_iter(&self, line: &str) -> Vec<Range<usize>> {
+        let mut spans = Vec::new();
+        let mut start = 0;
+        while let Some(span) = self.find_from(line, start) {
+            start = if span.is_empty() {
+                match line[span.end..].chars().next() {
+                    Some(c) => span.end + c.len_utf8(),
+                    None => line.len() + 1,
+                }
+            } else {
+                span.end
+            };
+            spans.push(span);
+            if start > line.len() {
+                break;
+            }
+        }
+        spans
+    }
+
+    fn find_from(&self, line: &str, from: usize) -> Option<Range<usize>> {
+        if self.whole_line {
+            if from > 0 {
+                return None;
+            }
+            return match self.match_len_at(line, 0) {
+                Some(len) if len == line.len() => Some(0..len),
+                _ => None,
+            };
+        }
+        if !self.ignore_case {
+            return self.find_literal(line, from);
+        }
+        let starts = line[from..]
+            .char_indices()
+            .map(|(i, _)| from + i)
+            .chain(std::iter::once(line.len()));
+        for start in starts {
+            let Some(len) = self.match_len_at(line, start) else {
+                continue;
+            };
+            let span = start..start + len;
+            if !self.whole_word || is_word_bounded(line, &span) {
+                return Some(span);
+            }
+        }
+        None
+    }
+
+    /// `find_from` for a case-sensitive query: `str::find` jumps from
+    /// candidate to candidate, and only those are checked for word
+    /// boundaries.
+    fn find_literal(&self, line: &str, from: usize) -> Option<Range<usize>> {
+        let mut start = from;
+        loop {
+            let found = start + line[start..].find(&self.query)?;
+            let span = found..found + self.query.len();
+            if !self.whole_word || is_word_bounded(line, &span) {
+                return Some(span);
+            }
+            // overlapping candidates may still be bounded
+            start = found + line[found..].chars().next()?.len_utf8();
+        }
+    }
+
+    /// Length in bytes of the query matched at `start`, if it matches there.
+    fn match_len_at(&self, line: &str, start: usize) -> Option<usize> {
+        let rest = &line[start..];
+        if !self.ignore_case {
+            return rest.starts_with(&self.query).then_some(self.query.len());
+        }
+        let mut query = self.query.chars().flat_map(char::to_lowercase).peekable();
+        let mut len = 0;
+        for c in rest.chars() {
+            if query.peek().is_none() {
+                break;
+            }
+            for lower in c.to_lowercase() {
+                if query.next() != Some(lower) {
+                    return None;
+                }
+            }
+            len += c.len_utf8();
+        }
+        query.peek().is_none().then_some(len)
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn is_word_bounded(line: &str, span: &Range<usize>) -> bool {
+    let before = line[..span.start].chars().next_back();
+    let after = line[span.end..].chars().next();
+    !before.is_some_and(is_word_char) && !after.is_some_and(is_word_char)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn whole_word() {
+        let matcher = Matcher::new("id").whole_word(true);
+        assert!(!matcher.is_match("void"));
+        assert!(!matcher.is_match("identity"));
+        assert!(!matcher.is_match("width"));
+        assert!(matcher.is_match("user id: 7"));
+        assert!(matcher.is_match("(id)"));
+        assert!(!matcher.is_match("_id"));
+        assert!(!matcher.is_match("idé"));
+    }
+
+    #[test]
+    fn whole_word_skips_earlier_partial_hits() {
+        let matcher = Matcher::new("id").whole_word(true);
+        assert_eq!(Some(8..10), matcher.find("void or id"));
+    }
+
+    #[test]
+    fn whole_word_tries_overlapping_candidates() {
+        assert_eq!(Some(3..4), Matcher::new("a").whole_word(true).find("aa a"));
+        // `match_indices` would go on after 1..4 and miss 3..6
+        assert_eq!(
+            Some(3..6),
+            Matcher::new("a a").whole_word(true).find("aa a a")
+        );
+        assert_eq!(vec![0..0, 2..2, 3..3], Matcher::new("").find_iter("éa"));
+    }
+
+    #[test]
+    fn whole_line() {
+        let matcher = Matcher::new("Pick Three.").whole_line(true);
+        assert!(matcher.is_match("Pick Three."));
+        assert!(!matcher.is_match("Pick Three. "));
+        assert!(!matcher.is_match("Pick"));
+    }
+
+    #[test]
+    fn options_combine_with_ignore_case() {
+        let word = Matcher::new("RUST").ignore_case(true).whole_word(true);
+        assert!(word.is_match("Rust:"));
+        assert!(!word.is_match("Trust me."));
+
+        let line = Matcher::new("pick three.")
+            .ignore_case(true)
+            .whole_line(true);
+        assert!(line.is_match("Pick Three."));
+    }
+
+    #[test]
+    fn find_iter_reports_spans_in_original_line() {
+        let matcher = Matcher::new("straße").ignore_case(true);
+        assert_eq!(vec![0..7, 8..15], matcher.find_iter("STRAßE Straße"));
+    }
+}
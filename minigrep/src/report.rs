@@ -0,0 +1,190 @@
+//!
+//! `--format html|markdown`: render search results as a report that can be
+//! pasted into a code review or a wiki page.
+//!
+
+use std::fmt::Write;
+
+use crate::Hit;
+
+/// How `run` prints its results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    /// The plain `n: line` listing.
+    #[default]
+    Text,
+    Html,
+    Markdown,
+}
+
+impl Format {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "text" => Some(Format::Text),
+            "html" => Some(Format::Html),
+            "markdown" | "md" => Some(Format::Markdown),
+            _ => None,
+        }
+    }
+}
+
+/// Render the hits of each file, grouped under the file name. Files without
+/// hits are left out. `Format::Text` is printed by `run` itself and renders
+/// nothing here.
+pub fn render(format: Format, files: &[(&str, &[Hit])]) -> String {
+    let files = files.iter().filter(|(_, hits)| !hits.is_empty());
+    let mut out = String::new();
+    match format {
+        Format::Text => {}
+        Format::Html => {
+            for (path, hits) in files {
+                html_file(&mut out, path, hits);
+            }
+        }
+        Format::Markdown => {
+            for (path, hits) in files {
+                markdown_file(&mut out, path, hits);
+            }
+        }
+    }
+    out
+}
+
+fn html_file(out: &mut String, path: &str, hits: &[Hit]) {
+    writeln!(out, "<section class=\"minigrep-file\">").unwrap();
+    writeln!(out, "<h3><code>{}</code></h3>", escape_html(path)).unwrap();
+    writeln!(out, "<table>").unwrap();
+    for hit in hits {
+        let mut line = String::new();
+        let mut last = 0;
+        for span in &hit.spans {
+            line.push_str(&escape_html(&hit.line[last..span.start]));
+            line.push_str("<mark>");
+            line.push_str(&escape_html(&hit.line[span.clone()]));
+            line.push_str("</mark>");
+            last = span.end;
+        }
+        line.push_str(&escape_html(&hit.line[last..]));
+        writeln!(
+            out,
+            "<tr><td class=\"line-number\">{}</td><td><code>{}</code></td></tr>",
+            hit.line_number, line
+        )
+        .unwrap();
+    }
+    writeln!(out, "</table>").unwrap();
+    writeln!(out, "</section>").unwrap();
+}
+
+fn markdown_file(out: &mut String, path: &str, hits: &[Hit]) {
+    writeln!(out, "### {}", escape_markdown(path)).unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "| Line | Text |").unwrap();
+    writeln!(out, "| ---: | --- |").unwrap();
+    for hit in hits {
+        let mut line = String::new();
+        let mut last = 0;
+        // `****` is not emphasis, so an empty match (an empty query) is
+        // left unmarked
+        for span in hit.spans.iter().filter(|span| !span.is_empty()) {
+            line.push_str(&escape_markdown(&hit.line[last..span.start]));
+            line.push_str("**");
+            line.push_str(&escape_markdown(&hit.line[span.clone()]));
+            line.push_str("**");
+            last = span.end;
+        }
+        line.push_str(&escape_markdown(&hit.line[last..]));
+        writeln!(out, "| {} | {} |", hit.line_number, line).unwrap();
+    }
+    writeln!(out).unwrap();
+}
+
+fn escape_html(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Backslash-escape everything that could start Markdown or inline HTML
+/// markup, including `|` so a line cannot break out of its table cell.
+fn escape_markdown(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        if matches!(
+            c,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '|' | '~' | '&' | '#'
+        ) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hits() -> Vec<Hit<'static>> {
+        vec![
+            Hit {
+                line_number: 3,
+                line_offset: 40,
+                line: "if a < b && id_ok {",
+                spans: vec![5..6, 12..14],
+            },
+            Hit {
+                line_number: 9,
+                line_offset: 120,
+                line: "x | y",
+                spans: vec![],
+            },
+        ]
+    }
+
+    #[test]
+    fn html_escapes_and_marks_spans() {
+        let hits = hits();
+        let html = render(Format::Html, &[("src/<a>.rs", &hits)]);
+        assert!(html.contains("<h3><code>src/&lt;a&gt;.rs</code></h3>"));
+        assert!(html.contains(
+            "<tr><td class=\"line-number\">3</td>\
+             <td><code>if a <mark>&lt;</mark> b &amp;&amp; <mark>id</mark>_ok {</code></td></tr>"
+        ));
+    }
+
+    #[test]
+    fn markdown_escapes_and_marks_spans() {
+        let hits = hits();
+        let md = render(Format::Markdown, &[("poem.txt", &hits)]);
+        assert!(md.starts_with("### poem.txt\n\n| Line | Text |\n| ---: | --- |\n"));
+        assert!(md.contains("| 3 | if a **\\<** b \\&\\& **id**\\_ok { |\n"));
+        assert!(md.contains("| 9 | x \\| y |\n"));
+    }
+
+    #[test]
+    fn markdown_leaves_empty_spans_unmarked() {
+        let hits = [Hit {
+            line_number: 1,
+            line_offset: 0,
+            line: "ab",
+            spans: vec![0..0, 1..1, 2..2],
+        }];
+        let md = render(Format::Markdown, &[("poem.txt", &hits)]);
+        assert!(md.contains("| 1 | ab |\n"));
+    }
+
+    #[test]
+    fn files_without_hits_are_skipped() {
+        assert_eq!("", render(Format::Markdown, &[("empty.txt", &[])]));
+    }
+}
@@ -0,0 +1,91 @@
+//!
+//! Where the text being searched comes from.
+//!
+//! Large regular files are memory-mapped, which saves copying the whole
+//! file into a `String` before searching it. Small files, pipes and stdin
+//! (`-`) are read into memory the usual way.
+//!
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+use std::str;
+
+use memmap2::Mmap;
+
+/// Files at least this big are memory-mapped. Below that, setting up the
+/// mapping costs more than the copy it saves.
+pub const MMAP_THRESHOLD: u64 = 1024 * 1024;
+
+/// The contents of one input, either mapped or read into a buffer.
+pub enum Source {
+    Mapped(Mmap),
+    Buffered(String),
+}
+
+impl Source {
+    /// Open `path`, choosing the backend automatically. `-` reads stdin.
+    /// With `allow_mmap` set to false the buffered path is always used.
+    pub fn open(path: &Path, allow_mmap: bool) -> io::Result<Self> {
+        if path == Path::new("-") {
+            let mut text = String::new();
+            io::stdin().lock().read_to_string(&mut text)?;
+            return Ok(Source::Buffered(text));
+        }
+        let mut file = File::open(path)?;
+        let metadata = file.metadata()?;
+        if allow_mmap && metadata.is_file() && metadata.len() >= MMAP_THRESHOLD {
+            // SAFETY: the mapping is read-only. As with every mmap-based
+            // tool, another process truncating the file while we search it
+            // is not something we can protect against.
+            let map = unsafe { Mmap::map(&file)? };
+            str::from_utf8(&map).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            return Ok(Source::Mapped(map));
+        }
+        let mut text = String::with_capacity(metadata.len() as usize);
+        file.read_to_string(&mut text)?;
+        Ok(Source::Buffered(text))
+    }
+
+    pub fn is_mapped(&self) -> bool {
+        matches!(self, Source::Mapped(_))
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            // SAFETY: `open` only builds `Mapped` after checking the bytes
+            // are valid UTF-8, and the mapping is never written to.
+            Source::Mapped(map) => unsafe { str::from_utf8_unchecked(map) },
+            Source::Buffered(text) => text,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, fs, process};
+
+    #[test]
+    fn small_files_are_buffered() {
+        let source = Source::open(Path::new("poem.txt"), true).unwrap();
+        assert!(!source.is_mapped());
+        assert!(source.as_str().starts_with("I'm nobody!"));
+    }
+
+    #[test]
+    fn large_files_are_mapped() {
+        let path = env::temp_dir().join(format!("minigrep-mmap-{}.txt", process::id()));
+        let line = "Are you nobody, too?\n";
+        let copies = MMAP_THRESHOLD as usize / line.len() + 1;
+        fs::write(&path, line.repeat(copies)).unwrap();
+
+        let mapped = Source::open(&path, true).unwrap();
+        let buffered = Source::open(&path, false).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(mapped.is_mapped());
+        assert!(!buffered.is_mapped());
+        assert_eq!(buffered.as_str(), mapped.as_str());
+    }
+}
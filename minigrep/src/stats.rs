@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Range;
+use std::time::Duration;
+
+/// Rows shown in the histogram of spellings.
+const HISTOGRAM_ROWS: usize = 10;
+/// Width of the longest histogram bar.
+const HISTOGRAM_WIDTH: usize = 40;
+
+/// What `--stats` reports once a search is done.
+#[derive(Debug, Default)]
+pub struct Stats {
+    pub files: usize,
+    pub bytes: u64,
+    pub lines_matched: usize,
+    pub matches: usize,
+    pub elapsed: Duration,
+    /// How often each spelling of the query was matched. The matcher only
+    /// finds the query itself, so the spellings can only differ in case.
+    counts: HashMap<String, usize>,
+}
+
+impl Stats {
+    pub fn add_file(&mut self, bytes: usize) {
+        self.files += 1;
+        self.bytes += bytes as u64;
+    }
+
+    /// Record one matching line together with its match spans.
+    pub fn add_line(&mut self, line: &str, spans: &[Range<usize>]) {
+        self.lines_matched += 1;
+        self.matches += spans.len();
+        for span in spans {
+            *self
+                .counts
+                .entry(line[span.clone()].to_string())
+                .or_insert(0) += 1;
+        }
+    }
+
+    /// The `n` most frequent spellings of the match, most frequent first.
+    /// Ties are broken alphabetically so the report is stable between runs.
+    pub fn most_common(&self, n: usize) -> Vec<(&str, usize)> {
+        let mut counts: Vec<(&str, usize)> = self
+            .counts
+            .iter()
+            .map(|(text, count)| (text.as_str(), *count))
+            .collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        counts.truncate(n);
+        counts
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} files searched", self.files)?;
+        writeln!(f, "{} bytes scanned", self.bytes)?;
+        writeln!(f, "{} lines matched", self.lines_matched)?;
+        writeln!(f, "{} matches", self.matches)?;
+        writeln!(f, "{:.3} seconds", self.elapsed.as_secs_f64())?;
+        // a single spelling, as in every case-sensitive search, needs no
+        // histogram
+        if self.counts.len() < 2 {
+            return Ok(());
+        }
+        let top = self.most_common(HISTOGRAM_ROWS);
+        let max = top[0].1;
+        writeln!(f)?;
+        writeln!(f, "spellings matched ({} distinct):", self.counts.len())?;
+        let label = top
+            .iter()
+            .map(|(text, _)| text.chars().count())
+            .max()
+            .unwrap_or(0);
+        for (text, count) in top {
+            let bar = (count * HISTOGRAM_WIDTH).div_ceil(max);
+            writeln!(
+                f,
+                "{:<label$}  {:>6}  {}",
+                text,
+                count,
+                "#".repeat(bar),
+                label = label
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Matcher;
+
+    fn add_line(stats: &mut Stats, matcher: &Matcher, line: &str) {
+        stats.add_line(line, &matcher.find_iter(line));
+    }
+
+    #[test]
+    fn counts_spellings() {
+        let matcher = Matcher::new("rust").ignore_case(true);
+        let mut stats = Stats::default();
+        stats.add_file(30);
+        add_line(&mut stats, &matcher, "Rust rust");
+        add_line(&mut stats, &matcher, "Trust RUST");
+        assert_eq!(2, stats.lines_matched);
+        assert_eq!(4, stats.matches);
+        assert_eq!(
+            vec![("rust", 2), ("RUST", 1), ("Rust", 1)],
+            stats.most_common(10)
+        );
+        assert_eq!(vec![("rust", 2)], stats.most_common(1));
+    }
+
+    #[test]
+    fn report_has_histogram_of_spellings() {
+        let matcher = Matcher::new("err").ignore_case(true);
+        let mut stats = Stats::default();
+        stats.add_file(15);
+        add_line(&mut stats, &matcher, "ERR err ERR Err");
+        let report = stats.to_string();
+        assert!(report.contains("1 files searched\n15 bytes scanned\n"));
+        assert!(report.contains("spellings matched (3 distinct):\n"));
+        assert!(report.contains(&format!("ERR       2  {}\n", "#".repeat(40))));
+        assert!(report.contains(&format!("Err       1  {}\n", "#".repeat(20))));
+    }
+
+    #[test]
+    fn one_spelling_has_no_histogram() {
+        let matcher = Matcher::new("err");
+        let mut stats = Stats::default();
+        add_line(&mut stats, &matcher, "err, err");
+        assert!(stats.to_string().ends_with("2 matches\n0.000 seconds\n"));
+    }
+}
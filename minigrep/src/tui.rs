@@ -0,0 +1,290 @@
+//!
+//! `--tui`: browse the results of a search interactively.
+//!
+//! The screen is drawn on stderr so that the `file:line` printed on exit
+//! can be piped straight into an editor, e.g.
+//! `code -g $(minigrep --tui TODO src/lib.rs)`.
+//!
+
+use std::io::{self, Write};
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Color, Print, ResetColor, SetAttribute, SetForegroundColor};
+use crossterm::terminal::{self, ClearType};
+use crossterm::{cursor, execute, queue};
+
+use crate::Config;
+
+/// Lines of context shown above and below the selected hit.
+const PREVIEW_CONTEXT: usize = 5;
+
+/// The state behind the TUI, kept apart from the drawing code so it can be
+/// exercised without a terminal.
+pub struct Browser<'a> {
+    cfg: Config,
+    lines: Vec<&'a str>,
+    /// Indexes into `lines` of every line matching the current query.
+    hits: Vec<usize>,
+    selected: usize,
+    /// First hit shown in the result list.
+    scroll: usize,
+}
+
+impl<'a> Browser<'a> {
+    pub fn new(cfg: &Config, contents: &'a str) -> Self {
+        let mut browser = Browser {
+            cfg: cfg.clone(),
+            lines: contents.lines().collect(),
+            hits: Vec::new(),
+            selected: 0,
+            scroll: 0,
+        };
+        browser.hits = browser.rescan();
+        browser
+    }
+
+    pub fn query(&self) -> &str {
+        &self.cfg.query
+    }
+
+    /// 1-based line numbers of the current hits.
+    pub fn hit_lines(&self) -> Vec<usize> {
+        self.hits.iter().map(|idx| idx + 1).collect()
+    }
+
+    /// 1-based line number of the selected hit.
+    pub fn selected_line(&self) -> Option<usize> {
+        self.hits.get(self.selected).map(|idx| idx + 1)
+    }
+
+    /// Replace the query and search again. When the new query only extends
+    /// the old one, the previous hits are narrowed down instead of scanning
+    /// every line again: each line matching `abc` also matches `ab`. That
+    /// does not hold for `-w` and `-x`, which always rescan.
+    pub fn set_query(&mut self, query: &str) {
+        let narrowing =
+            query.starts_with(&self.cfg.query) && !self.cfg.whole_word && !self.cfg.whole_line;
+        self.cfg.query = query.to_string();
+        self.hits = if narrowing {
+            let matcher = self.cfg.matcher();
+            self.hits
+                .iter()
+                .copied()
+                .filter(|&idx| matcher.is_match(self.lines[idx]))
+                .collect()
+        } else {
+            self.rescan()
+        };
+        self.selected = 0;
+        self.scroll = 0;
+    }
+
+    fn rescan(&self) -> Vec<usize> {
+        let matcher = self.cfg.matcher();
+        (0..self.lines.len())
+            .filter(|&idx| matcher.is_match(self.lines[idx]))
+            .collect()
+    }
+
+    /// Move the selection by `delta` hits, clamped to the result list.
+    pub fn move_selection(&mut self, delta: isize) {
+        if self.hits.is_empty() {
+            return;
+        }
+        let last = self.hits.len() - 1;
+        self.selected = self.selected.saturating_add_signed(delta).min(last);
+    }
+
+    /// Keep the selected hit inside a list view `height` rows tall.
+    fn scroll_into_view(&mut self, height: usize) {
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if height > 0 && self.selected >= self.scroll + height {
+            self.scroll = self.selected + 1 - height;
+        }
+    }
+
+    /// Indexes of the lines around the selected hit.
+    pub fn preview(&self) -> std::ops::Range<usize> {
+        match self.hits.get(self.selected) {
+            Some(&idx) => {
+                idx.saturating_sub(PREVIEW_CONTEXT)
+                    ..(idx + PREVIEW_CONTEXT + 1).min(self.lines.len())
+            }
+            None => 0..0,
+        }
+    }
+}
+
+/// Run the interactive browser until the user picks a hit (Enter) or gives
+/// up (Esc / Ctrl-C). Returns the 1-based line number of the pick.
+pub fn browse(cfg: &Config, contents: &str) -> io::Result<Option<usize>> {
+    let mut browser = Browser::new(cfg, contents);
+    let mut out = io::stderr();
+    terminal::enable_raw_mode()?;
+    execute!(out, terminal::EnterAlternateScreen, cursor::Hide)?;
+    let picked = event_loop(&mut browser, &mut out);
+    execute!(out, cursor::Show, terminal::LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()?;
+    picked
+}
+
+fn event_loop(browser: &mut Browser, out: &mut impl Write) -> io::Result<Option<usize>> {
+    loop {
+        draw(browser, out)?;
+        let Event::Key(KeyEvent {
+            code,
+            modifiers,
+            kind: KeyEventKind::Press,
+            ..
+        }) = event::read()?
+        else {
+            continue;
+        };
+        let page = terminal::size()?.1 as isize / 2;
+        match code {
+            KeyCode::Enter => return Ok(browser.selected_line()),
+            KeyCode::Esc => return Ok(None),
+            KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => return Ok(None),
+            KeyCode::Up => browser.move_selection(-1),
+            KeyCode::Down => browser.move_selection(1),
+            KeyCode::PageUp => browser.move_selection(-page),
+            KeyCode::PageDown => browser.move_selection(page),
+            KeyCode::Backspace => {
+                let mut query = browser.query().to_string();
+                query.pop();
+                browser.set_query(&query);
+            }
+            KeyCode::Char(c) => {
+                let query = format!("{}{}", browser.query(), c);
+                browser.set_query(&query);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Layout: the query prompt on the first row, the result list on the top
+/// half of the screen and a preview of the selected hit underneath.
+fn draw(browser: &mut Browser, out: &mut impl Write) -> io::Result<()> {
+    let (width, height) = terminal::size()?;
+    let width = width as usize;
+    let list_height = (height as usize).saturating_sub(2) / 2;
+    browser.scroll_into_view(list_height);
+    let matcher = browser.cfg.matcher();
+
+    queue!(out, terminal::Clear(ClearType::All), cursor::MoveTo(0, 0))?;
+    let status = format!(
+        "{} > {}   [{} hits]",
+        browser.cfg.filename,
+        browser.query(),
+        browser.hits.len()
+    );
+    queue!(out, Print(truncate(&status, width)))?;
+
+    for (row, pos) in (browser.scroll..browser.hits.len())
+        .take(list_height)
+        .enumerate()
+    {
+        let idx = browser.hits[pos];
+        let text = format!("{:>6}: {}", idx + 1, browser.lines[idx]);
+        queue!(out, cursor::MoveTo(0, row as u16 + 1))?;
+        if pos == browser.selected {
+            queue!(out, SetAttribute(Attribute::Reverse))?;
+        }
+        queue!(
+            out,
+            Print(truncate(&text, width)),
+            SetAttribute(Attribute::Reset)
+        )?;
+    }
+
+    let top = list_height as u16 + 1;
+    queue!(out, cursor::MoveTo(0, top), Print("─".repeat(width)))?;
+    let selected = browser.hits.get(browser.selected).copied();
+    for (row, idx) in browser.preview().enumerate() {
+        let line = truncate(browser.lines[idx], width.saturating_sub(8));
+        queue!(out, cursor::MoveTo(0, top + 1 + row as u16))?;
+        if Some(idx) == selected {
+            queue!(out, SetAttribute(Attribute::Bold))?;
+        }
+        queue!(out, Print(format!("{:>6}: ", idx + 1)))?;
+        let mut last = 0;
+        for span in matcher.find_iter(line) {
+            queue!(
+                out,
+                Print(&line[last..span.start]),
+                SetForegroundColor(Color::Red),
+                Print(&line[span.clone()]),
+                ResetColor
+            )?;
+            last = span.end;
+        }
+        queue!(out, Print(&line[last..]), SetAttribute(Attribute::Reset))?;
+    }
+    out.flush()
+}
+
+/// Cut `s` down to at most `width` characters.
+fn truncate(s: &str, width: usize) -> &str {
+    match s.char_indices().nth(width) {
+        Some((end, _)) => &s[..end],
+        None => s,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENTS: &str = "\
+Rust:
+Safe, Fast, Productive.
+Pick Three.
+Trust me.
+";
+
+    fn browser(query: &str) -> Browser<'static> {
+        let cfg = Config {
+            query: query.to_string(),
+            case_insensitive: true,
+            ..Default::default()
+        };
+        Browser::new(&cfg, CONTENTS)
+    }
+
+    #[test]
+    fn refining_the_query_narrows_hits() {
+        let mut browser = browser("t");
+        assert_eq!(vec![1, 2, 3, 4], browser.hit_lines());
+        browser.set_query("tr");
+        assert_eq!(vec![4], browser.hit_lines());
+        browser.set_query("r");
+        assert_eq!(vec![1, 2, 3, 4], browser.hit_lines());
+    }
+
+    #[test]
+    fn selection_is_clamped() {
+        let mut browser = browser("t");
+        browser.move_selection(-3);
+        assert_eq!(Some(1), browser.selected_line());
+        browser.move_selection(10);
+        assert_eq!(Some(4), browser.selected_line());
+        browser.set_query("xyz");
+        browser.move_selection(1);
+        assert_eq!(None, browser.selected_line());
+    }
+
+    #[test]
+    fn preview_surrounds_selection() {
+        let mut browser = browser("t");
+        browser.move_selection(2);
+        assert_eq!(0..4, browser.preview());
+    }
+
+    #[test]
+    fn truncate_counts_chars() {
+        assert_eq!("Зд", truncate("Здравствуйте", 2));
+        assert_eq!("ab", truncate("ab", 5));
+    }
+}